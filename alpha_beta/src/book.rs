@@ -0,0 +1,229 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Read, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use rand::Rng;
+
+use crate::{
+    file::{self, read_bytes, Format},
+    AlphaBetaSearch, GameModel, ModelScore, MoveIndex, SearchConfig, SearchLimits,
+    TranspositionTable, DEFAULT_TABLE_MB,
+};
+
+const FORMAT: Format = Format {
+    magic: *b"ABBK",
+    version: 1,
+    name: "an opening book",
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookMove {
+    // The move's index in `legal_moves`, like the transposition table's best move.
+    pub index: MoveIndex,
+    // How often to play the move, relative to the position's other book moves.
+    pub weight: u16,
+}
+
+// Opening moves keyed by position, so the search can play them without searching. Results from the
+// book have depth 0.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Book {
+    entries: HashMap<u64, Vec<BookMove>>,
+}
+
+impl Book {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Adds weight to a move, adding the move if it isn't in the book yet.
+    pub fn add(&mut self, key: u64, index: MoveIndex, weight: u16) {
+        let moves = self.entries.entry(key).or_default();
+        match moves.iter_mut().find(|m| m.index == index) {
+            Some(m) => m.weight = m.weight.saturating_add(weight),
+            None => {
+                moves.push(BookMove { index, weight });
+                moves.sort_by_key(|m| m.index);
+            }
+        }
+    }
+
+    pub fn moves(&self, key: u64) -> &[BookMove] {
+        self.entries.get(&key).map_or(&[], |moves| moves.as_slice())
+    }
+
+    // Picks one of the position's moves, with a chance in proportion to its weight.
+    pub fn choose(&self, key: u64, rng: &mut impl Rng) -> Option<MoveIndex> {
+        let moves = self.moves(key);
+        let total = moves.iter().map(|m| m.weight as u32).sum::<u32>();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = rng.gen_range(0..total);
+        for m in moves {
+            if roll < m.weight as u32 {
+                return Some(m.index);
+            }
+            roll -= m.weight as u32;
+        }
+        None
+    }
+
+    // A record per move: the position key, the move index as a byte and its weight. Fails on move
+    // indices past 255.
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        let mut records = self
+            .entries
+            .iter()
+            .flat_map(|(&key, moves)| moves.iter().map(move |m| (key, *m)))
+            .collect::<Vec<_>>();
+        records.sort_by_key(|&(key, m)| (key, m.index));
+        if records.iter().any(|(_, m)| m.index > u8::MAX as MoveIndex) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "move index too large for the book",
+            ));
+        }
+        FORMAT.write_header(&mut writer)?;
+        writer.write_all(&(records.len() as u32).to_le_bytes())?;
+        for (key, m) in records {
+            writer.write_all(&key.to_le_bytes())?;
+            writer.write_all(&[m.index as u8])?;
+            writer.write_all(&m.weight.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn read(mut reader: impl Read) -> io::Result<Book> {
+        FORMAT.read_header(&mut reader)?;
+        let count = u32::from_le_bytes(read_bytes(&mut reader)?);
+        let mut book = Book::default();
+        for _ in 0..count {
+            let record = read_bytes::<11>(&mut reader)?;
+            let key = u64::from_le_bytes(record[..8].try_into().unwrap());
+            let weight = u16::from_le_bytes(record[9..].try_into().unwrap());
+            book.add(key, record[8] as MoveIndex, weight);
+        }
+        Ok(book)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        file::save(path, |writer| self.write(writer))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Book> {
+        file::load(path, Book::read)
+    }
+}
+
+// Fills a book by searching every position in the first few plies of the game, offline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookBuilder {
+    // Positions fewer than this many plies from the start get book moves.
+    pub plies: usize,
+    // How deep each move is searched.
+    pub search_depth: usize,
+    // Moves scoring within this much of the best go in the book too, weighted by how close they are.
+    pub margin: i32,
+}
+
+impl Default for BookBuilder {
+    fn default() -> Self {
+        BookBuilder {
+            plies: 4,
+            search_depth: 10,
+            margin: 0,
+        }
+    }
+}
+
+impl BookBuilder {
+    pub fn build<Model: GameModel>(&self, model: &mut Model) -> Book {
+        let mut book = Book::default();
+        let tt = Arc::new(TranspositionTable::new(DEFAULT_TABLE_MB));
+        let mut seen = HashSet::new();
+        self.add_position(model, 0, &mut book, &tt, &mut seen);
+        book
+    }
+
+    fn add_position<Model: GameModel>(
+        &self,
+        model: &mut Model,
+        ply: usize,
+        book: &mut Book,
+        tt: &Arc<TranspositionTable>,
+        seen: &mut HashSet<u64>,
+    ) {
+        let key = model.zobrist_key();
+        if ply >= self.plies || model.outcome().is_some() || !seen.insert(key) {
+            return;
+        }
+        // Every move within the margin gets an exact score from the root of the search.
+        let config = SearchConfig {
+            root_margin: self.margin.saturating_add(1),
+            ..Default::default()
+        };
+        let mut search =
+            AlphaBetaSearch::with_table(self.search_depth, config, tt.clone(), ModelScore);
+        search.continue_search(model, SearchLimits::default());
+        let moves = model.legal_moves();
+        if let Some(result) = search.best_so_far {
+            for (m, score) in result.near_best {
+                let index = moves.iter().position(|&legal| legal == m).unwrap();
+                let gap = result.score.saturating_sub(score);
+                if index <= u8::MAX as usize {
+                    let weight = (self.margin - gap).saturating_add(1).min(u16::MAX as i32);
+                    book.add(key, index, weight as u16);
+                }
+            }
+        }
+        for m in moves.iter() {
+            model.apply(m);
+            self.add_position(model, ply + 1, book, tt, seen);
+            model.undo(m);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::Book;
+
+    #[test]
+    fn book_file() {
+        let mut book = Book::default();
+        book.add(7, 3, 2);
+        book.add(7, 1, 1);
+        book.add(7, 3, 2);
+        book.add(u64::MAX, 0, 9);
+        assert_eq!(book.len(), 2);
+        assert_eq!(book.moves(7).len(), 2);
+        assert_eq!(book.moves(8), &[]);
+
+        let mut bytes = Vec::new();
+        book.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 9 + 3 * 11);
+        assert_eq!(Book::read(bytes.as_slice()).unwrap(), book);
+        assert!(Book::read(&bytes[1..]).is_err());
+        let mut wide = book.clone();
+        wide.add(7, 256, 1);
+        assert!(wide.write(&mut Vec::new()).is_err());
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let picks = (0..100)
+            .filter(|_| book.choose(7, &mut rng) == Some(3))
+            .count();
+        assert!(picks > 60);
+        assert_eq!(book.choose(u64::MAX, &mut rng), Some(0));
+        assert_eq!(book.choose(8, &mut rng), None);
+    }
+}
@@ -0,0 +1,240 @@
+use std::hash::Hash;
+
+use rand::Rng;
+
+use crate::{Evaluator, GameModel, ModelScore, SearchLimits, SearchStats, WIN_SCORE};
+
+// A game where some positions are decided by chance, such as a dice roll, instead of by a player.
+// Chance events don't change whose turn it is, so a chance node is scored for the player who moves
+// after it.
+pub trait ChanceModel: GameModel {
+    type ChanceEvent: PartialEq + Copy + Hash;
+
+    // The events that could happen next, with probabilities adding up to 1. Empty unless the position
+    // is a chance node, in which case `legal_moves` isn't used.
+    fn chance_events(&self) -> Vec<(Self::ChanceEvent, f64)>;
+    fn apply_chance(&mut self, event: &Self::ChanceEvent);
+    fn undo_chance(&mut self, event: &Self::ChanceEvent);
+
+    // Picks what happens at a chance node, for playing games out. A seeded `rng` plays the same game
+    // every time.
+    fn sample_chance(&self, rng: &mut impl Rng) -> Option<Self::ChanceEvent> {
+        let events = self.chance_events();
+        let mut roll = rng.gen::<f64>();
+        for &(event, p) in events.iter() {
+            if roll < p {
+                return Some(event);
+            }
+            roll -= p;
+        }
+        // Rounding can leave a sliver of probability over, which goes to the last event.
+        events.last().map(|&(event, _)| event)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpectimaxConfig {
+    // Every score the search can see lies within these bounds, evaluations included. Chance nodes
+    // prune once the events left can't bring the average back into the window, so tighter bounds
+    // prune more.
+    pub bounds: (i32, i32),
+}
+
+impl Default for ExpectimaxConfig {
+    fn default() -> Self {
+        ExpectimaxConfig {
+            bounds: (-WIN_SCORE, WIN_SCORE),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExpectimaxResult<Move> {
+    pub depth: usize,
+    // The expected score for the player to move.
+    pub score: i32,
+    pub m: Move,
+}
+
+// Expectimax with Star1 pruning at chance nodes and alpha-beta at the players' nodes. Depth counts
+// the players' moves, so chance events in between are free.
+#[derive(Default)]
+pub struct ExpectimaxSearch<Eval = ModelScore> {
+    config: ExpectimaxConfig,
+    evaluator: Eval,
+    pub stats: SearchStats,
+}
+
+impl ExpectimaxSearch {
+    pub fn new(config: ExpectimaxConfig) -> ExpectimaxSearch {
+        ExpectimaxSearch::with_evaluator(config, ModelScore)
+    }
+}
+
+impl<Eval> ExpectimaxSearch<Eval> {
+    pub fn with_evaluator(config: ExpectimaxConfig, evaluator: Eval) -> ExpectimaxSearch<Eval> {
+        ExpectimaxSearch {
+            config,
+            evaluator,
+            stats: SearchStats::default(),
+        }
+    }
+
+    // Returns the move from the deepest depth that completed, or None at chance nodes.
+    pub fn search<Model>(
+        &mut self,
+        model: &mut Model,
+        max_depth: usize,
+        limits: impl Into<SearchLimits>,
+    ) -> Option<ExpectimaxResult<Model::GameMove>>
+    where
+        Model: ChanceModel,
+        Eval: Evaluator<Model>,
+    {
+        let limits = limits.into();
+        let mut best = None;
+        for depth in 1..=max_depth {
+            if best.is_some() && !limits.can_start_depth(self.stats.nodes) {
+                break;
+            }
+            match self.search_root(model, depth, &limits) {
+                Ok(Some(result)) => best = Some(result),
+                Ok(None) => return None,
+                Err(()) => break,
+            }
+        }
+        best
+    }
+
+    fn search_root<Model>(
+        &mut self,
+        model: &mut Model,
+        depth: usize,
+        limits: &SearchLimits,
+    ) -> Result<Option<ExpectimaxResult<Model::GameMove>>, ()>
+    where
+        Model: ChanceModel,
+        Eval: Evaluator<Model>,
+    {
+        self.stats.nodes += 1;
+        if model.outcome().is_some() || !model.chance_events().is_empty() {
+            return Ok(None);
+        }
+        let mut alpha = -i32::MAX;
+        let mut best = None;
+        for m in model.legal_moves() {
+            model.apply(&m);
+            let score = self.expectimax(model, depth - 1, 1, limits, -i32::MAX, -alpha);
+            model.undo(&m);
+            let score = -score?;
+            if best.is_none() || score > alpha {
+                alpha = score;
+                best = Some(m);
+            }
+        }
+        Ok(best.map(|m| ExpectimaxResult {
+            depth,
+            score: alpha,
+            m,
+        }))
+    }
+
+    // Fail-hard, like the rest of the crate's searches. Err means the limits ran out.
+    fn expectimax<Model>(
+        &mut self,
+        model: &mut Model,
+        depth: usize,
+        ply: usize,
+        limits: &SearchLimits,
+        mut alpha: i32,
+        beta: i32,
+    ) -> Result<i32, ()>
+    where
+        Model: ChanceModel,
+        Eval: Evaluator<Model>,
+    {
+        self.stats.nodes += 1;
+        if limits.exceeded(self.stats.nodes) {
+            return Err(());
+        }
+        if let Some(outcome) = model.outcome() {
+            self.stats.leaf_evaluations += 1;
+            return Ok(outcome.score_at(ply).clamp(alpha, beta));
+        }
+        let events = model.chance_events();
+        if !events.is_empty() {
+            return self.chance(model, events, depth, ply, limits, alpha, beta);
+        }
+        let moves = model.legal_moves();
+        if depth == 0 || moves.is_empty() {
+            self.stats.leaf_evaluations += 1;
+            let (lower, upper) = self.config.bounds;
+            let score = self.evaluator.evaluate(model).clamp(lower, upper);
+            return Ok(score.clamp(alpha, beta));
+        }
+        for (searched, m) in moves.iter().enumerate() {
+            model.apply(m);
+            let score = self.expectimax(model, depth - 1, ply + 1, limits, -beta, -alpha);
+            model.undo(m);
+            let score = -score?;
+            if score >= beta {
+                self.stats.add_beta_cutoff(searched);
+                return Ok(beta);
+            }
+            alpha = alpha.max(score);
+        }
+        Ok(alpha)
+    }
+
+    // Star1: each event gets the narrowest window that could still move the average into
+    // (alpha, beta), assuming the events after it score as well or as badly as the bounds allow.
+    #[allow(clippy::too_many_arguments)]
+    fn chance<Model>(
+        &mut self,
+        model: &mut Model,
+        events: Vec<(Model::ChanceEvent, f64)>,
+        depth: usize,
+        ply: usize,
+        limits: &SearchLimits,
+        alpha: i32,
+        beta: i32,
+    ) -> Result<i32, ()>
+    where
+        Model: ChanceModel,
+        Eval: Evaluator<Model>,
+    {
+        let (lower, upper) = (self.config.bounds.0 as f64, self.config.bounds.1 as f64);
+        // The expected score so far, and the probability of the events not searched yet.
+        let mut sum = 0.;
+        let mut remaining = 1.;
+        for (event, p) in events {
+            remaining = (remaining - p).max(0.);
+            // Rounded outwards, so the window is never narrower than it needs to be.
+            let event_alpha = ((alpha as f64 - sum - remaining * upper) / p)
+                .max(lower)
+                .floor();
+            let event_beta = ((beta as f64 - sum - remaining * lower) / p)
+                .min(upper)
+                .ceil();
+            model.apply_chance(&event);
+            let score = self.expectimax(
+                model,
+                depth,
+                ply,
+                limits,
+                event_alpha as i32,
+                event_beta as i32,
+            );
+            model.undo_chance(&event);
+            let score = score? as f64;
+            if score <= event_alpha && event_alpha > lower {
+                return Ok(alpha);
+            }
+            if score >= event_beta && event_beta < upper {
+                return Ok(beta);
+            }
+            sum += p * score;
+        }
+        Ok((sum.round() as i32).clamp(alpha, beta))
+    }
+}
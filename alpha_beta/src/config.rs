@@ -0,0 +1,76 @@
+use crate::DEFAULT_TABLE_MB;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchConfig {
+    pub table_mb: usize,
+    // Half-width of the window around the previous depth's score. None searches every depth with the full window.
+    pub aspiration_window: Option<i32>,
+    // How many times wider the window gets each time a search falls outside it.
+    pub aspiration_widening: i32,
+    // Root moves scoring less than this much below the best get exact scores too, so a bot can pick
+    // between them. Searching them costs extra nodes, so 0 only finds the best.
+    pub root_margin: i32,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            table_mb: DEFAULT_TABLE_MB,
+            aspiration_window: None,
+            aspiration_widening: 4,
+            root_margin: 0,
+        }
+    }
+}
+
+impl SearchConfig {
+    // The window for the next depth, given the score of the last one.
+    pub fn aspiration(&self, score: i32) -> (i32, i32) {
+        match self.aspiration_window {
+            Some(delta) => (
+                score.saturating_sub(delta).max(-i32::MAX),
+                score.saturating_add(delta),
+            ),
+            None => (-i32::MAX, i32::MAX),
+        }
+    }
+
+    // Widens whichever side of `window` the score fell outside of.
+    pub fn widen(&self, window: (i32, i32), score: i32) -> (i32, i32) {
+        let (alpha, beta) = window;
+        let delta = (beta.saturating_sub(alpha) / 2)
+            .max(1)
+            .saturating_mul(self.aspiration_widening.max(2));
+        if score <= alpha {
+            (score.saturating_sub(delta).max(-i32::MAX), beta)
+        } else {
+            (alpha, score.saturating_add(delta))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SearchConfig;
+
+    #[test]
+    fn windows() {
+        let config = SearchConfig {
+            aspiration_window: Some(50),
+            ..Default::default()
+        };
+        assert_eq!(config.aspiration(100), (50, 150));
+        assert_eq!(config.widen((50, 150), 50), (-150, 150));
+        assert_eq!(config.widen((50, 150), 150), (50, 350));
+        assert_eq!(config.widen((-150, 150), -150), (-750, 150));
+        assert_eq!(config.widen((-i32::MAX, 150), 150), (-i32::MAX, i32::MAX));
+        assert_eq!(
+            config.aspiration(-i32::MAX + 1),
+            (-i32::MAX, -i32::MAX + 51)
+        );
+        assert_eq!(
+            SearchConfig::default().aspiration(100),
+            (-i32::MAX, i32::MAX)
+        );
+    }
+}
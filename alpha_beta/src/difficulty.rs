@@ -0,0 +1,101 @@
+use std::{hash::Hash, sync::Arc, time::Duration};
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    AlphaBetaResult, AlphaBetaSearch, GameModel, Noise, SearchConfig, SearchLimits,
+    TranspositionTable,
+};
+
+// How well a bot plays. Weaker bots search less, misjudge positions and sometimes just play
+// something else.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Difficulty {
+    pub max_depth: usize,
+    // How long the bot may think about each move. None lets it finish `max_depth`.
+    pub time: Option<Duration>,
+    // The most the evaluation is off by either way, as `Noise::amount`.
+    pub noise: i32,
+    // The chance of playing a random legal move instead of searching's choice.
+    pub blunder_chance: f64,
+    // The bot picks at random between moves scoring less than this much below the best.
+    pub margin: i32,
+}
+
+impl Difficulty {
+    pub const EASY: Difficulty = Difficulty {
+        max_depth: 2,
+        time: Some(Duration::from_millis(500)),
+        noise: 150,
+        blunder_chance: 0.15,
+        margin: 100,
+    };
+
+    pub const MEDIUM: Difficulty = Difficulty {
+        max_depth: 4,
+        time: Some(Duration::from_secs(1)),
+        noise: 50,
+        blunder_chance: 0.03,
+        margin: 20,
+    };
+
+    // Plays the best move it can find at the depth the bot has always searched to, unless the caller
+    // limits its time.
+    pub const HARD: Difficulty = Difficulty {
+        max_depth: 6,
+        time: None,
+        noise: 0,
+        blunder_chance: 0.0,
+        margin: 0,
+    };
+
+    // A search that scores positions with noise added to `evaluator` and keeps the near-best moves.
+    // Give each game its own seed, so the bot doesn't make the same mistakes every time.
+    pub fn search<Move: PartialEq + Copy + Hash, Eval>(
+        &self,
+        config: SearchConfig,
+        tt: Arc<TranspositionTable>,
+        evaluator: Eval,
+        seed: u64,
+    ) -> AlphaBetaSearch<Move, Noise<Eval>> {
+        let config = SearchConfig {
+            root_margin: self.margin,
+            ..config
+        };
+        let evaluator = Noise {
+            evaluator,
+            amount: self.noise,
+            seed,
+        };
+        AlphaBetaSearch::with_table(self.max_depth, config, tt, evaluator)
+    }
+
+    // Starts the clock on the next move.
+    pub fn limits(&self) -> SearchLimits {
+        self.time.map(SearchLimits::from).unwrap_or_default()
+    }
+
+    // Picks the move to play from a finished search of `model`. Book moves are played as they are.
+    pub fn choose<Model: GameModel>(
+        &self,
+        model: &Model,
+        result: &AlphaBetaResult<Model::GameMove>,
+        rng: &mut impl Rng,
+    ) -> Model::GameMove {
+        if result.from_book {
+            return result.m;
+        }
+        if rng.gen_bool(self.blunder_chance.clamp(0.0, 1.0)) {
+            if let Some(&m) = model.legal_moves().choose(rng) {
+                return m;
+            }
+        }
+        result.near_best.choose(rng).map_or(result.m, |&(m, _)| m)
+    }
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty::MEDIUM
+    }
+}
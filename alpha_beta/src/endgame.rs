@@ -0,0 +1,192 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use crate::{
+    file::{self, invalid, read_bytes, Format},
+    GameModel, Outcome, MAX_PLIES,
+};
+
+const FORMAT: Format = Format {
+    magic: *b"ABEG",
+    version: 1,
+    name: "an endgame table",
+};
+
+// Exact outcomes for solved positions, keyed by position. The search looks positions up before
+// searching them, so anything in the table is played perfectly.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EndgameTable {
+    outcomes: HashMap<u64, Outcome>,
+}
+
+impl EndgameTable {
+    pub fn len(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outcomes.is_empty()
+    }
+
+    // Only decided outcomes can go in the table, so heuristic ones are ignored.
+    pub fn insert(&mut self, key: u64, outcome: Outcome) {
+        if outcome.is_decided() {
+            self.outcomes.insert(key, outcome);
+        }
+    }
+
+    // The outcome for the player to move.
+    pub fn probe(&self, key: u64) -> Option<Outcome> {
+        self.outcomes.get(&key).copied()
+    }
+
+    // A record per position: the key, then the outcome as an i16 from `encode`.
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        let mut records = self.outcomes.iter().collect::<Vec<_>>();
+        records.sort_by_key(|&(&key, _)| key);
+        FORMAT.write_header(&mut writer)?;
+        writer.write_all(&(records.len() as u64).to_le_bytes())?;
+        for (key, outcome) in records {
+            writer.write_all(&key.to_le_bytes())?;
+            writer.write_all(&encode(*outcome).to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn read(mut reader: impl Read) -> io::Result<EndgameTable> {
+        FORMAT.read_header(&mut reader)?;
+        let count = u64::from_le_bytes(read_bytes(&mut reader)?);
+        let mut table = EndgameTable::default();
+        for _ in 0..count {
+            let record = read_bytes::<10>(&mut reader)?;
+            let key = u64::from_le_bytes(record[..8].try_into().unwrap());
+            let outcome = decode(i16::from_le_bytes(record[8..].try_into().unwrap()))
+                .ok_or_else(|| invalid("outcome out of range"))?;
+            table.insert(key, outcome);
+        }
+        Ok(table)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        file::save(path, |writer| self.write(writer))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<EndgameTable> {
+        file::load(path, EndgameTable::read)
+    }
+}
+
+// Plies until a win (positive) or a loss (negative), offset by one so 0 is a draw.
+fn encode(outcome: Outcome) -> i16 {
+    match outcome {
+        Outcome::Win(plies) => plies as i16 + 1,
+        Outcome::Loss(plies) => -(plies as i16 + 1),
+        _ => 0,
+    }
+}
+
+fn decode(value: i16) -> Option<Outcome> {
+    if value == 0 {
+        return Some(Outcome::Draw);
+    }
+    let plies = value.unsigned_abs() as usize - 1;
+    match value {
+        _ if plies > MAX_PLIES as usize => None,
+        _ if value > 0 => Some(Outcome::Win(plies)),
+        _ => Some(Outcome::Loss(plies)),
+    }
+}
+
+// Solves every position reachable from a model by walking the whole game tree, so it only suits
+// small games, or positions close enough to the end of a bigger one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EndgameBuilder {
+    // Positions fewer than this many plies from the model are solved but left out of the table,
+    // since the search rarely gets to look them up.
+    pub min_plies: usize,
+}
+
+impl EndgameBuilder {
+    pub fn build<Model: GameModel>(&self, model: &mut Model) -> EndgameTable {
+        self.build_all(std::slice::from_mut(model))
+    }
+
+    // One table for every position in `models`, solving what they have in common once.
+    pub fn build_all<Model: GameModel>(&self, models: &mut [Model]) -> EndgameTable {
+        let mut solved = HashMap::new();
+        let mut table = EndgameTable::default();
+        for model in models {
+            self.solve(model, 0, &mut solved, &mut table);
+        }
+        table
+    }
+
+    fn solve<Model: GameModel>(
+        &self,
+        model: &mut Model,
+        ply: usize,
+        solved: &mut HashMap<u64, Outcome>,
+        table: &mut EndgameTable,
+    ) -> Outcome {
+        if let Some(outcome) = model.outcome() {
+            return outcome;
+        }
+        let key = model.zobrist_key();
+        if let Some(&outcome) = solved.get(&key) {
+            return outcome;
+        }
+        let mut best = None;
+        let mut exact = true;
+        for m in model.legal_moves() {
+            model.apply(&m);
+            let child = self.solve(model, ply + 1, solved, table);
+            model.undo(&m);
+            exact &= child.is_decided();
+            best = best.max(Some(-child.score_at(1)));
+        }
+        // A game that stops without an outcome is scored as it stands, which isn't exact, and neither
+        // is anything that can lead to it. Those are kept out of the table.
+        let outcome = match best {
+            Some(score) if exact => Outcome::from_exact_score(score),
+            Some(score) => Outcome::Heuristic(score),
+            None => Outcome::Heuristic(model.score()),
+        };
+        solved.insert(key, outcome);
+        if ply >= self.min_plies {
+            table.insert(key, outcome);
+        }
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Outcome;
+
+    use super::{decode, encode, EndgameTable};
+
+    #[test]
+    fn endgame_file() {
+        for outcome in [Outcome::Win(0), Outcome::Loss(7), Outcome::Draw] {
+            assert_eq!(decode(encode(outcome)), Some(outcome));
+        }
+        assert_eq!(decode(i16::MAX), None);
+
+        let mut table = EndgameTable::default();
+        table.insert(3, Outcome::Win(5));
+        table.insert(u64::MAX, Outcome::Draw);
+        table.insert(4, Outcome::Heuristic(10));
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.probe(3), Some(Outcome::Win(5)));
+        assert_eq!(table.probe(4), None);
+
+        let mut bytes = Vec::new();
+        table.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 13 + 2 * 10);
+        assert_eq!(EndgameTable::read(bytes.as_slice()).unwrap(), table);
+        assert!(EndgameTable::read(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
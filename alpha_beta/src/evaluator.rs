@@ -0,0 +1,50 @@
+use crate::GameModel;
+
+// Scores positions the search won't look past, so the same model can be searched with different
+// heuristics. Finished games are scored from their outcome and never reach the evaluator.
+pub trait Evaluator<Model: GameModel> {
+    // The score for the player to move, where higher is better for them.
+    fn evaluate(&self, model: &Model) -> i32;
+}
+
+impl<Model: GameModel, F: Fn(&Model) -> i32> Evaluator<Model> for F {
+    fn evaluate(&self, model: &Model) -> i32 {
+        self(model)
+    }
+}
+
+// The model's own `score`, which is what the search uses unless it's given something else.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct ModelScore;
+
+impl<Model: GameModel> Evaluator<Model> for ModelScore {
+    fn evaluate(&self, model: &Model) -> i32 {
+        model.score()
+    }
+}
+
+// Adds up to `amount` either way to another evaluator's score, to make a bot weaker. The noise comes
+// from the position's key, so a position scores the same every time it's reached and the
+// transposition table stays consistent. Different seeds make different mistakes.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Noise<Eval> {
+    pub evaluator: Eval,
+    pub amount: i32,
+    pub seed: u64,
+}
+
+impl<Model: GameModel, Eval: Evaluator<Model>> Evaluator<Model> for Noise<Eval> {
+    fn evaluate(&self, model: &Model) -> i32 {
+        let score = self.evaluator.evaluate(model);
+        if self.amount <= 0 {
+            return score;
+        }
+        // SplitMix64's finaliser, to spread similar keys over the whole range.
+        let mut x = model.zobrist_key() ^ self.seed;
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+        x ^= x >> 31;
+        let span = 2 * self.amount as u64 + 1;
+        score.saturating_add((x % span) as i32 - self.amount)
+    }
+}
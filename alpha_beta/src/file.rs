@@ -0,0 +1,55 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+// Every file the crate saves starts with four magic bytes and a version. Everything after that is
+// little endian.
+pub(crate) struct Format {
+    pub magic: [u8; 4],
+    pub version: u8,
+    // What the file holds, for the error when it's something else.
+    pub name: &'static str,
+}
+
+impl Format {
+    pub fn write_header(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(&self.magic)?;
+        writer.write_all(&[self.version])
+    }
+
+    pub fn read_header(&self, reader: &mut impl Read) -> io::Result<()> {
+        let header = read_bytes::<5>(reader)?;
+        if header[..4] != self.magic || header[4] != self.version {
+            return Err(invalid(&format!("not {}", self.name)));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub(crate) fn read_bytes<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+pub(crate) fn save(
+    path: impl AsRef<Path>,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(&mut writer)?;
+    writer.flush()
+}
+
+pub(crate) fn load<T>(
+    path: impl AsRef<Path>,
+    read: impl FnOnce(BufReader<File>) -> io::Result<T>,
+) -> io::Result<T> {
+    read(BufReader::new(File::open(path)?))
+}
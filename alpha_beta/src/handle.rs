@@ -0,0 +1,165 @@
+use std::{
+    hash::Hash,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::{
+    AlphaBetaResult, AlphaBetaSearch, Evaluator, GameModel, MctsBudget, MctsSearch, ModelScore,
+    SearchLimits,
+};
+
+// The result of the deepest completed depth, shared with whoever is polling the search.
+pub(crate) type SharedResult<Move> = Arc<Mutex<Option<AlphaBetaResult<Move>>>>;
+
+// A search running on its own thread, so a game loop can poll it each frame instead of blocking on it.
+// Dropping the handle cancels the search.
+pub struct SearchHandle<Move, Eval = ModelScore> {
+    key: u64,
+    stop: Arc<AtomicBool>,
+    latest: SharedResult<Move>,
+    thread: Option<JoinHandle<AlphaBetaSearch<Move, Eval>>>,
+}
+
+impl<Move, Eval> SearchHandle<Move, Eval>
+where
+    Move: PartialEq + Copy + Hash + Send + 'static,
+    Eval: Clone + Send + 'static,
+{
+    // Searches a copy of `model` with `threads` threads until the search completes or hits `limits`.
+    // If the limits already have a stop flag, cancelling the handle sets that one.
+    pub fn spawn<Model>(
+        mut search: AlphaBetaSearch<Move, Eval>,
+        model: &Model,
+        limits: impl Into<SearchLimits>,
+        threads: usize,
+    ) -> SearchHandle<Move, Eval>
+    where
+        Model: GameModel<GameMove = Move> + Clone + Send + 'static,
+        Eval: Evaluator<Model>,
+    {
+        let limits = limits.into();
+        let stop = limits.stop.clone().unwrap_or_default();
+        let limits = limits.with_stop(stop.clone());
+        let latest = Arc::new(Mutex::new(search.best_so_far.clone()));
+        search.shared = Some(latest.clone());
+        let key = model.zobrist_key();
+        let mut model = model.clone();
+        let thread = thread::spawn(move || {
+            search.continue_parallel_search(&mut model, limits, threads);
+            search.shared = None;
+            search
+        });
+        SearchHandle {
+            key,
+            stop,
+            latest,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl<Move: Clone, Eval> SearchHandle<Move, Eval> {
+    // The key of the position being searched, to tell whether the result still applies.
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    pub fn best_so_far(&self) -> Option<AlphaBetaResult<Move>> {
+        self.latest.lock().unwrap().clone()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_none_or(|thread| thread.is_finished())
+    }
+
+    pub fn cancel(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    // Waits for the search to stop and hands it back, so it can be continued or inspected.
+    pub fn join(mut self) -> Option<AlphaBetaSearch<Move, Eval>> {
+        self.thread.take().and_then(|thread| thread.join().ok())
+    }
+}
+
+impl<Move, Eval> Drop for SearchHandle<Move, Eval> {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+// The same for Monte Carlo tree search. The tree is shared rather than handed back, so whoever
+// started the search can keep it for their next move while the result is polled.
+pub struct MctsHandle<Move> {
+    key: u64,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<Option<Move>>>,
+}
+
+impl<Move: PartialEq + Copy + Send + 'static> MctsHandle<Move> {
+    // Searches a copy of `model` until the budget runs out or the handle is cancelled. A search
+    // still holding the tree from an earlier, cancelled handle finishes first.
+    pub fn spawn<Model, Eval>(
+        search: Arc<Mutex<MctsSearch<Move, Eval>>>,
+        model: &Model,
+        budget: MctsBudget,
+    ) -> MctsHandle<Move>
+    where
+        Model: GameModel<GameMove = Move> + Clone + Send + 'static,
+        Eval: Evaluator<Model> + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let key = model.zobrist_key();
+        let mut model = model.clone();
+        let thread = {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                search
+                    .lock()
+                    .unwrap()
+                    .search_until(&mut model, budget, &stop)
+            })
+        };
+        MctsHandle {
+            key,
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl<Move> MctsHandle<Move> {
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_none_or(|thread| thread.is_finished())
+    }
+
+    pub fn cancel(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    // Waits for the search to stop and returns the move it settled on.
+    pub fn join(mut self) -> Option<Move> {
+        self.thread
+            .take()
+            .and_then(|thread| thread.join().ok())
+            .flatten()
+    }
+}
+
+impl<Move> Drop for MctsHandle<Move> {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,337 @@
+use std::{hash::Hash, sync::Arc, time::Instant};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    mcts::rollout, AlphaBetaSearch, Evaluator, GameModel, MctsBudget, MctsConfig, ModelScore,
+    MoveStats, SearchConfig, SearchLimits, TranspositionTable,
+};
+
+// A game where players can't see everything, such as the cards in each other's hands. Bots search
+// the view of the player to move instead of the model, so they can't play on what they shouldn't
+// know.
+pub trait HiddenInformation: GameModel {
+    type View: InformationSet<Model = Self>;
+
+    // What the player to move knows: everything public, their own hidden state and what they've
+    // seen happen so far.
+    fn view(&self) -> Self::View;
+}
+
+// One player's knowledge of a game, which stands for every position they can't tell apart.
+pub trait InformationSet {
+    type Model: GameModel;
+
+    // A full position that agrees with the view, with the hidden parts filled in at random. The
+    // player to move has the same legal moves in all of them.
+    fn determinize(&self, rng: &mut impl Rng) -> Self::Model;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeterminizedConfig {
+    // How many positions to sample from the view and search.
+    pub samples: usize,
+    pub depth: usize,
+    pub seed: u64,
+    // For each sample's search. The root margin is ignored, since every root move needs a score.
+    pub search: SearchConfig,
+}
+
+impl Default for DeterminizedConfig {
+    fn default() -> Self {
+        DeterminizedConfig {
+            samples: 20,
+            depth: 4,
+            seed: 0,
+            search: SearchConfig {
+                table_mb: 1,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+// Perfect information Monte Carlo: searches positions sampled from the view as if everything were
+// visible, and plays the move with the best average score across them. Cheap and often strong, but
+// it assumes it'll find out the hidden state next move, so it never plays to learn or to hide it.
+pub struct DeterminizedSearch<Move, Eval = ModelScore> {
+    pub config: DeterminizedConfig,
+    evaluator: Eval,
+    rng: StdRng,
+    tt: Arc<TranspositionTable>,
+    // Each root move's summed score and how many samples scored it.
+    scores: Vec<(Move, i64, u32)>,
+    // Samples searched by the last search.
+    pub samples: usize,
+    pub nodes: u64,
+}
+
+impl<Move: PartialEq + Copy + Hash> DeterminizedSearch<Move> {
+    pub fn new(config: DeterminizedConfig) -> DeterminizedSearch<Move> {
+        DeterminizedSearch::with_evaluator(config, ModelScore)
+    }
+}
+
+impl<Move: PartialEq + Copy + Hash, Eval: Clone> DeterminizedSearch<Move, Eval> {
+    pub fn with_evaluator(
+        config: DeterminizedConfig,
+        evaluator: Eval,
+    ) -> DeterminizedSearch<Move, Eval> {
+        DeterminizedSearch {
+            config,
+            evaluator,
+            rng: StdRng::seed_from_u64(config.seed),
+            tt: Arc::new(TranspositionTable::new(config.search.table_mb)),
+            scores: Vec::new(),
+            samples: 0,
+            nodes: 0,
+        }
+    }
+
+    // Each root move's average score over the samples of the last search.
+    pub fn move_scores(&self) -> Vec<(Move, f64)> {
+        self.scores
+            .iter()
+            .map(|&(m, sum, count)| (m, sum as f64 / count as f64))
+            .collect()
+    }
+
+    pub fn best_move(&self) -> Option<Move> {
+        self.move_scores()
+            .into_iter()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(m, _)| m)
+    }
+
+    // Searches samples until there have been `config.samples` or the limits run out, which always
+    // leaves at least one unless the search is stopped partway through the first.
+    pub fn search<View>(&mut self, view: &View, limits: impl Into<SearchLimits>) -> Option<Move>
+    where
+        View: InformationSet,
+        View::Model: GameModel<GameMove = Move>,
+        Eval: Evaluator<View::Model>,
+    {
+        let limits = limits.into();
+        let config = SearchConfig {
+            root_margin: i32::MAX,
+            ..self.config.search
+        };
+        self.scores.clear();
+        self.samples = 0;
+        self.nodes = 0;
+        while self.samples < self.config.samples {
+            if self.samples > 0 && !limits.can_start_depth(self.nodes) {
+                break;
+            }
+            let mut model = view.determinize(&mut self.rng);
+            // Samples differ in what's hidden, which the keys may not cover.
+            self.tt.clear();
+            let mut search = AlphaBetaSearch::with_table(
+                self.config.depth,
+                config,
+                self.tt.clone(),
+                self.evaluator.clone(),
+            );
+            // Node limits count every sample together.
+            let sample_limits = SearchLimits {
+                max_nodes: limits.max_nodes.map(|max| max.saturating_sub(self.nodes)),
+                ..limits.clone()
+            };
+            search.continue_search(&mut model, sample_limits);
+            self.nodes += search.stats.nodes;
+            let Some(result) = search.best_so_far else {
+                break;
+            };
+            self.samples += 1;
+            for (m, score) in result.near_best {
+                match self.scores.iter_mut().find(|(scored, _, _)| *scored == m) {
+                    Some((_, sum, count)) => {
+                        *sum += score as i64;
+                        *count += 1;
+                    }
+                    None => self.scores.push((m, score as i64, 1)),
+                }
+            }
+        }
+        self.best_move()
+    }
+}
+
+struct InfoNode<Move> {
+    m: Option<Move>,
+    children: Vec<usize>,
+    visits: u32,
+    // How many times the node's move was legal when its parent was selected from.
+    availability: u32,
+    // Summed results for the player who made `m`.
+    wins: f64,
+}
+
+impl<Move> InfoNode<Move> {
+    fn new(m: Option<Move>) -> InfoNode<Move> {
+        InfoNode {
+            m,
+            children: Vec::new(),
+            visits: 0,
+            availability: 0,
+            wins: 0.,
+        }
+    }
+}
+
+// Single-observer information set MCTS. Every iteration samples a position from the view and walks
+// one shared tree of move sequences with it, only following moves that are legal in the sample, so
+// what's learned is about the view rather than any one guess at what's hidden.
+pub struct IsmctsSearch<Move, Eval = ModelScore> {
+    pub config: MctsConfig,
+    evaluator: Eval,
+    nodes: Vec<InfoNode<Move>>,
+    rng: StdRng,
+}
+
+impl<Move: PartialEq + Copy> IsmctsSearch<Move> {
+    pub fn new(config: MctsConfig) -> IsmctsSearch<Move> {
+        IsmctsSearch::with_evaluator(config, ModelScore)
+    }
+}
+
+impl<Move: PartialEq + Copy, Eval> IsmctsSearch<Move, Eval> {
+    pub fn with_evaluator(config: MctsConfig, evaluator: Eval) -> IsmctsSearch<Move, Eval> {
+        IsmctsSearch {
+            config,
+            evaluator,
+            nodes: Vec::new(),
+            rng: StdRng::seed_from_u64(config.seed),
+        }
+    }
+
+    pub fn root_visits(&self) -> u32 {
+        self.nodes.first().map_or(0, |root| root.visits)
+    }
+
+    pub fn move_stats(&self) -> Vec<MoveStats<Move>> {
+        match self.nodes.first() {
+            Some(root) => root
+                .children
+                .iter()
+                .map(|&child| {
+                    let node = &self.nodes[child];
+                    MoveStats {
+                        m: node.m.unwrap(),
+                        visits: node.visits,
+                        value: node.wins / node.visits.max(1) as f64,
+                    }
+                })
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn best_move(&self) -> Option<Move> {
+        self.move_stats()
+            .into_iter()
+            .max_by_key(|stats| stats.visits)
+            .map(|stats| stats.m)
+    }
+
+    // Starts a new tree every time, since the view changes with what the player sees as well as the
+    // moves played.
+    pub fn search<View>(&mut self, view: &View, budget: MctsBudget) -> Option<Move>
+    where
+        View: InformationSet,
+        View::Model: GameModel<GameMove = Move>,
+        Eval: Evaluator<View::Model>,
+    {
+        self.nodes = vec![InfoNode::new(None)];
+        let start = Instant::now();
+        let mut iterations = 0;
+        loop {
+            let done = match budget {
+                MctsBudget::Time(time) => start.elapsed() > time,
+                MctsBudget::Iterations(limit) => iterations >= limit,
+            };
+            if done {
+                break;
+            }
+            self.iterate(view);
+            iterations += 1;
+        }
+        self.best_move()
+    }
+
+    fn iterate<View>(&mut self, view: &View)
+    where
+        View: InformationSet,
+        View::Model: GameModel<GameMove = Move>,
+        Eval: Evaluator<View::Model>,
+    {
+        let mut model = view.determinize(&mut self.rng);
+        let mut path = vec![0];
+        let mut node = 0;
+        while model.outcome().is_none() {
+            let legal = model.legal_moves();
+            let available = self.nodes[node]
+                .children
+                .iter()
+                .copied()
+                .filter(|&child| legal.contains(&self.nodes[child].m.unwrap()))
+                .collect::<Vec<_>>();
+
+            // Expansion: add one move this sample allows that the tree hasn't tried yet.
+            let untried = legal
+                .iter()
+                .copied()
+                .filter(|&m| {
+                    !available
+                        .iter()
+                        .any(|&child| self.nodes[child].m == Some(m))
+                })
+                .collect::<Vec<_>>();
+            if let Some(&m) = untried.choose(&mut self.rng) {
+                model.apply(&m);
+                let child = self.nodes.len();
+                self.nodes.push(InfoNode::new(Some(m)));
+                self.nodes[node].children.push(child);
+                path.push(child);
+                break;
+            }
+            if available.is_empty() {
+                break;
+            }
+
+            // Selection, among the children this sample allows.
+            for &child in available.iter() {
+                self.nodes[child].availability += 1;
+            }
+            node = self.select(&available);
+            model.apply(&self.nodes[node].m.unwrap());
+            path.push(node);
+        }
+
+        // Simulation, scored for the player to move at the new node.
+        let mut value = rollout(&mut model, &self.config, &self.evaluator, &mut self.rng);
+
+        // Backpropagation: each node's wins belong to the player who moved into it.
+        for &index in path.iter().rev() {
+            value = 1. - value;
+            let node = &mut self.nodes[index];
+            node.visits += 1;
+            node.wins += value;
+        }
+    }
+
+    // UCT, with how often each move was available standing in for the parent's visits.
+    fn select(&self, available: &[usize]) -> usize {
+        let uct = |child: usize| {
+            let node = &self.nodes[child];
+            let visits = node.visits.max(1) as f64;
+            let log_available = (node.availability.max(1) as f64).ln();
+            node.wins / visits + self.config.exploration * (log_available / visits).sqrt()
+        };
+        available
+            .iter()
+            .copied()
+            .max_by(|&a, &b| uct(a).total_cmp(&uct(b)))
+            .unwrap()
+    }
+}
@@ -1,353 +1,654 @@
-
 use std::{
-    collections::{hash_map::DefaultHasher, HashMap},
-    hash::{Hash, Hasher},
-    time::{Duration, SystemTime},
+    fmt::{Debug, Display},
+    hash::Hash,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
+use rand::{rngs::StdRng, SeedableRng};
+
+mod book;
+mod chance;
+mod config;
+mod difficulty;
+mod endgame;
+mod evaluator;
+mod file;
+mod handle;
+mod hidden;
+mod limits;
+mod mcts;
+mod observer;
+mod ordering;
+mod outcome;
+mod parallel;
+mod stats;
+#[cfg(test)]
 mod test;
-
-#[derive(Default)]
-struct TranspositionTable {
-    table: HashMap<u64, i32>,
-}
-
-#[derive(Default)]
-struct PVSTable<Move> {
-    table: HashMap<u64, Move>,
-}
-
-impl<Move: PartialEq + Copy> PVSTable<Move> {
-    fn sort(&self, board: &impl Hash, moves: &mut Vec<Move>) {
-        let mut hasher = DefaultHasher::new();
-        board.hash(&mut hasher);
-        let hash = hasher.finish();
-        if let Some(m) = self.table.get(&hash) {
-            if let Some(index) = moves.iter().position(|r| r == m) {
-                moves[index] = moves[0];
-                moves[0] = *m;
-            }
-        }
-    }
-
-    fn get(&self, board: &impl Hash) -> Option<Move> {
-        let mut hasher = DefaultHasher::new();
-        board.hash(&mut hasher);
-        let hash = hasher.finish();
-        self.table.get(&hash).copied()
-    }
-
-    fn add_val(&mut self, board: &impl Hash, m: Move) {
-        let mut hasher = DefaultHasher::new();
-        board.hash(&mut hasher);
-        let hash = hasher.finish();
-        self.table.insert(hash, m);
+pub mod testing;
+mod training;
+mod transposition;
+mod zobrist;
+
+pub use book::{Book, BookBuilder, BookMove};
+pub use chance::{ChanceModel, ExpectimaxConfig, ExpectimaxResult, ExpectimaxSearch};
+pub use config::SearchConfig;
+pub use difficulty::Difficulty;
+pub use endgame::{EndgameBuilder, EndgameTable};
+pub use evaluator::{Evaluator, ModelScore, Noise};
+pub use handle::{MctsHandle, SearchHandle};
+pub use hidden::{
+    DeterminizedConfig, DeterminizedSearch, HiddenInformation, InformationSet, IsmctsSearch,
+};
+pub use limits::SearchLimits;
+pub use mcts::{MctsBudget, MctsConfig, MctsSearch, MoveStats, Rollout};
+pub use observer::{PrintObserver, SearchObserver};
+pub use ordering::MoveOrdering;
+pub use outcome::{Outcome, MAX_PLIES, WIN_SCORE};
+pub use stats::{DepthStats, SearchStats};
+pub use training::{Dataset, Features, LinearEvaluator, Sample, SelfPlay, Tuner, Weights};
+pub use transposition::{Bound, Entry, TranspositionTable, DEFAULT_TABLE_MB};
+pub use zobrist::{ZobristKey, ZobristTable, DEFAULT_SEED};
+
+pub type MoveIndex = usize;
+
+// Deep enough to reach the end of any game the solver is meant for, and small enough to store in the table.
+pub const SOLVE_DEPTH: usize = u8::MAX as usize;
+
+// The score of a position the search won't look past, for the player to move.
+fn evaluate<Model: GameModel>(model: &Model, evaluator: &impl Evaluator<Model>, ply: usize) -> i32 {
+    match model.outcome() {
+        Some(outcome) => outcome.score_at(ply),
+        None => evaluator.evaluate(model),
     }
 }
 
-impl TranspositionTable {
-    fn depth_hash(board: &impl Hash, depth: usize) -> u64 {
-        let mut hasher = DefaultHasher::new();
-        board.hash(&mut hasher);
-        depth.hash(&mut hasher);
-        hasher.finish()
-    }
-
-    fn score(&self, board: &impl Hash, depth: usize) -> Option<i32> {
-        self.table
-            .get(&TranspositionTable::depth_hash(board, depth))
-            .copied()
+// Evaluates a position at the search horizon, first playing out any noisy moves so the score isn't
+// taken halfway through an exchange. The player to move may always stand on the current score instead.
+fn quiescence<Model: GameModel>(
+    model: &mut Model,
+    evaluator: &impl Evaluator<Model>,
+    ply: usize,
+    mut alpha: i32,
+    beta: i32,
+) -> i32 {
+    let mut best = evaluate(model, evaluator, ply);
+    if model.outcome().is_some() || ply >= MAX_PLIES as usize {
+        return best;
     }
-
-    fn add_val(&mut self, board: &impl Hash, depth: usize, score: i32) {
-        self.table
-            .insert(TranspositionTable::depth_hash(board, depth), score);
+    for m in model.noisy_moves() {
+        if best >= beta {
+            break;
+        }
+        alpha = alpha.max(best);
+        model.apply(&m);
+        let score = -quiescence(model, evaluator, ply + 1, -beta, -alpha);
+        model.undo(&m);
+        best = best.max(score);
     }
+    best
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AlphaBetaResult<Move> {
     pub depth: usize,
     pub score: i32,
+    pub outcome: Outcome,
     pub m: Move,
+    // The expected line of play, starting with `m`.
+    pub pv: Vec<Move>,
+    // Every root move scoring less than `SearchConfig::root_margin` below the best, `m` included, with
+    // its score. Empty when the margin is 0.
+    pub near_best: Vec<(Move, i32)>,
+    // Played from the opening book without searching, so the score and outcome are only placeholders.
+    pub from_book: bool,
 }
 
-type MoveIndex = usize;
 pub struct AlphaBetaState<Move> {
-    pub moves: Vec<Move>,
+    pub moves: Vec<(MoveIndex, Move)>,
     pub alpha: i32,
     pub beta: i32,
-    pub best_move: Option<Move>,
+    pub best_move: Option<(MoveIndex, Move)>,
+    pub searched: usize,
+    // The move being searched beat a null window, so it is being searched again with the full one.
+    pub re_search: bool,
+    // Moves this close to alpha are searched exactly as well. Only the root has a margin.
+    pub margin: i32,
 }
 
 impl<Move> AlphaBetaState<Move> {
-    pub fn new(moves: Vec<Move>, alpha: i32, beta: i32) -> AlphaBetaState<Move> {
+    pub fn new(moves: Vec<(MoveIndex, Move)>, alpha: i32, beta: i32) -> AlphaBetaState<Move> {
         AlphaBetaState {
             moves,
             alpha,
             beta,
             best_move: None,
+            searched: 0,
+            re_search: false,
+            margin: 0,
+        }
+    }
+
+    // The lowest score that still needs to be exact.
+    fn floor(&self) -> i32 {
+        self.alpha.saturating_sub(self.margin).max(-i32::MAX)
+    }
+
+    // The first move gets the full window. Later ones are expected to fail low, so they get a null window to prove it.
+    fn child_window(&self) -> (i32, i32) {
+        let alpha = self.floor();
+        if self.searched == 0 || self.re_search {
+            (-self.beta, -alpha)
+        } else {
+            (-alpha - 1, -alpha)
         }
     }
 }
 
 #[derive(Default)]
-pub struct AlphaBetaSearch<Move> {
+pub struct AlphaBetaSearch<Move, Eval = ModelScore> {
     pub complete: bool,
     target_depth: usize,
     pub best_so_far: Option<AlphaBetaResult<Move>>,
-    tt: TranspositionTable,
-    pvs: PVSTable<Move>,
+    pub stats: SearchStats,
+    config: SearchConfig,
+    evaluator: Eval,
+    observer: Option<Box<dyn SearchObserver<Move>>>,
+    // With the generator that picks between book moves.
+    book: Option<(Arc<Book>, StdRng)>,
+    endgame: Option<Arc<EndgameTable>>,
+    tt: Arc<TranspositionTable>,
+    ordering: MoveOrdering<Move>,
+    // Where a `SearchHandle` watches for new results.
+    shared: Option<handle::SharedResult<Move>>,
     current_depth: usize,
+    // The root window for the current depth, narrowed around the last score when aspiration is on.
+    window: (i32, i32),
     state: Vec<AlphaBetaState<Move>>,
+    root_move: Option<Move>,
+    // Root moves that scored within the margin at the current depth, which may since have fallen out of it.
+    root_scores: Vec<(Move, i32)>,
+    // Nodes and time already spent on the current depth by earlier calls.
+    depth_nodes: u64,
+    depth_time: Duration,
 }
 
-impl<Move: PartialEq + Copy> AlphaBetaSearch<Move> {
+impl<Move: PartialEq + Copy + Hash> AlphaBetaSearch<Move> {
     fn new(target_depth: usize) -> AlphaBetaSearch<Move> {
-        AlphaBetaSearch {
-            complete: false,
+        AlphaBetaSearch::with_table_size(target_depth, DEFAULT_TABLE_MB)
+    }
+
+    pub fn with_table_size(target_depth: usize, table_mb: usize) -> AlphaBetaSearch<Move> {
+        AlphaBetaSearch::with_config(
             target_depth,
-            best_so_far: None,
-            tt: TranspositionTable::default(),
-            pvs: PVSTable {
-                table: HashMap::new(),
+            SearchConfig {
+                table_mb,
+                ..Default::default()
             },
-            current_depth: 2,
-            state: Vec::default(),
-        }
+        )
     }
 
+    pub fn with_config(target_depth: usize, config: SearchConfig) -> AlphaBetaSearch<Move> {
+        AlphaBetaSearch::with_evaluator(target_depth, config, ModelScore)
+    }
+
+    // `limits` may be a `Duration`, which is the time budget for this call.
     pub fn start_search(
         model: &mut impl GameModel<GameMove = Move>,
         target_depth: usize,
-        budget: Duration,
+        limits: impl Into<SearchLimits>,
     ) -> AlphaBetaSearch<Move> {
         let mut search_data = AlphaBetaSearch::new(target_depth);
-        search_data.search(model, SystemTime::now(), budget);
+        search_data.search(model, &limits.into());
         search_data
     }
+}
 
-    pub fn continue_search(
-        &mut self,
-        model: &mut impl GameModel<GameMove = Move>,
-        budget: Duration,
-    ) {
-        self.search(model, SystemTime::now(), budget);
+impl<Move: PartialEq + Copy + Hash, Eval> AlphaBetaSearch<Move, Eval> {
+    // Scores the horizon with `evaluator` instead of the model's own `score`.
+    pub fn with_evaluator(
+        target_depth: usize,
+        config: SearchConfig,
+        evaluator: Eval,
+    ) -> AlphaBetaSearch<Move, Eval> {
+        let tt = Arc::new(TranspositionTable::new(config.table_mb));
+        AlphaBetaSearch::with_table(target_depth, config, tt, evaluator)
     }
 
-    fn generate_moves(&mut self, model: &mut impl GameModel<GameMove = Move>) -> Vec<Move> {
-        let mut moves = model.legal_moves();
-        self.pvs.sort(model, &mut moves);
-        moves.reverse();
-        moves
+    // Shares `tt`, such as with the same player's searches of earlier moves. Calling `new_search` on
+    // it before each move lets the old entries give way.
+    pub fn with_table(
+        target_depth: usize,
+        config: SearchConfig,
+        tt: Arc<TranspositionTable>,
+        evaluator: Eval,
+    ) -> AlphaBetaSearch<Move, Eval> {
+        AlphaBetaSearch {
+            complete: false,
+            target_depth,
+            best_so_far: None,
+            stats: SearchStats::default(),
+            config,
+            evaluator,
+            observer: None,
+            book: None,
+            endgame: None,
+            tt,
+            ordering: MoveOrdering::default(),
+            shared: None,
+            // Depth 1 costs about as much as depth 2, so it's skipped unless it's all that was asked for.
+            current_depth: target_depth.clamp(1, 2),
+            window: (-i32::MAX, i32::MAX),
+            state: Vec::default(),
+            root_move: None,
+            root_scores: Vec::new(),
+            depth_nodes: 0,
+            depth_time: Duration::ZERO,
+        }
+    }
+
+    pub fn set_observer(&mut self, observer: impl SearchObserver<Move> + 'static) {
+        self.observer = Some(Box::new(observer));
+    }
+
+    // Positions in the book are answered from it without searching. The same seed picks the same
+    // book moves.
+    pub fn set_book(&mut self, book: Arc<Book>, seed: u64) {
+        self.book = Some((book, StdRng::seed_from_u64(seed)));
     }
 
-    fn search(
+    // Positions in the table are scored from it without searching, whatever the depth left.
+    pub fn set_endgame(&mut self, endgame: Arc<EndgameTable>) {
+        self.endgame = Some(endgame);
+    }
+
+    pub fn continue_search<Model>(&mut self, model: &mut Model, limits: impl Into<SearchLimits>)
+    where
+        Model: GameModel<GameMove = Move>,
+        Eval: Evaluator<Model>,
+    {
+        self.search(model, &limits.into());
+    }
+
+    // Moves are popped off the end of the state, so the best candidate goes last.
+    fn generate_moves(
         &mut self,
         model: &mut impl GameModel<GameMove = Move>,
-        start: SystemTime,
-        budget: Duration,
-    ) {
+        entry: Option<Entry>,
+        ply: usize,
+    ) -> Vec<(MoveIndex, Move)> {
+        let mut moves = self.ordering.order(model, entry, ply);
+        moves.reverse();
+        moves
+    }
+
+    // Follows the exact entries' best moves from the root, leaving the model as it was.
+    fn principal_variation(&self, model: &mut impl GameModel<GameMove = Move>) -> Vec<Move> {
+        let mut pv = Vec::new();
+        while pv.len() < self.current_depth {
+            let next = match self.tt.probe(model.zobrist_key()) {
+                Some(entry) if entry.bound == Bound::Exact => entry
+                    .best_move()
+                    .and_then(|index| model.legal_moves().get(index).copied()),
+                _ => None,
+            };
+            match next {
+                Some(m) => {
+                    model.apply(&m);
+                    pv.push(m);
+                }
+                None => break,
+            }
+        }
+        for m in pv.iter().rev() {
+            model.undo(m);
+        }
+        pv
+    }
+
+    fn search<Model>(&mut self, model: &mut Model, limits: &SearchLimits)
+    where
+        Model: GameModel<GameMove = Move>,
+        Eval: Evaluator<Model>,
+    {
         if self.complete {
             return;
         }
-        while self.current_depth <= self.target_depth {
+        if self.best_so_far.is_none() && self.state.is_empty() {
+            if let Some(m) = self.book_move(model) {
+                self.set_best(AlphaBetaResult {
+                    depth: 0,
+                    score: 0,
+                    outcome: Outcome::Heuristic(0),
+                    m,
+                    pv: vec![m],
+                    near_best: Vec::new(),
+                    from_book: true,
+                });
+                self.complete = true;
+                return;
+            }
+        }
+        let target_depth = limits.max_depth.map_or(self.target_depth, |max_depth| {
+            max_depth.min(self.target_depth)
+        });
+        let mut segment_start = Instant::now();
+        while self.current_depth <= target_depth {
             if self.state.is_empty() {
-                let new_state =
-                    AlphaBetaState::new(self.generate_moves(model), -i32::MAX, i32::MAX);
+                // Always finish one depth, so there's a move to play.
+                if self.best_so_far.is_some() && !limits.can_start_depth(self.stats.nodes) {
+                    self.give_up(segment_start);
+                    return;
+                }
+                self.stats.nodes += 1;
+                let entry = self.tt.probe(model.zobrist_key());
+                let (alpha, beta) = self.window;
+                let mut new_state =
+                    AlphaBetaState::new(self.generate_moves(model, entry, 0), alpha, beta);
+                new_state.margin = self.config.root_margin;
                 self.state.push(new_state);
+                self.root_scores.clear();
             }
 
-            match self.search_internal(model, start, budget, 0) {
-                Ok(_) => {
-                    println!("Solved depth: {}", self.current_depth);
-                    self.best_so_far = Some(AlphaBetaResult {
-                        depth: self.current_depth,
-                        score: self.tt.score(&model, self.current_depth).unwrap(),
-                        m: self.pvs.get(&model).unwrap(),
-                    });
+            match self.search_internal(model, limits, 0) {
+                Ok(score) => {
+                    // The score fell outside the aspiration window, so it's only a bound. Search the depth again.
+                    let (alpha, beta) = self.window;
+                    if (score <= alpha && alpha > -i32::MAX) || (score >= beta && beta < i32::MAX) {
+                        self.stats.researches += 1;
+                        self.window = self.config.widen(self.window, score);
+                        self.root_move = None;
+                        continue;
+                    }
+                    match self.root_move.take() {
+                        Some(m) => {
+                            let now = Instant::now();
+                            self.stats.depths.push(DepthStats {
+                                depth: self.current_depth,
+                                nodes: self.stats.nodes - self.depth_nodes,
+                                time: self.depth_time + now.duration_since(segment_start),
+                            });
+                            self.depth_nodes = self.stats.nodes;
+                            self.depth_time = Duration::ZERO;
+                            segment_start = now;
+
+                            let result = AlphaBetaResult {
+                                depth: self.current_depth,
+                                score,
+                                outcome: Outcome::from_score(score),
+                                m,
+                                pv: self.principal_variation(model),
+                                near_best: self
+                                    .root_scores
+                                    .iter()
+                                    .filter(|&&(_, s)| {
+                                        s > score.saturating_sub(self.config.root_margin)
+                                    })
+                                    .copied()
+                                    .collect(),
+                                from_book: false,
+                            };
+                            if let Some(observer) = self.observer.as_mut() {
+                                observer.on_depth_complete(&result, &self.stats);
+                            }
+                            self.set_best(result);
+                        }
+                        // There are no legal moves, so deeper searches won't find any either.
+                        None => {
+                            self.complete = true;
+                            return;
+                        }
+                    }
+                    self.window = self.config.aspiration(score);
                     self.current_depth += 1;
                 }
                 Err(_) => {
+                    self.give_up(segment_start);
                     return;
                 }
             }
         }
-        if self.current_depth > self.target_depth {
-            self.complete = true;
+        // Stopping at `max_depth` leaves the deeper depths for a later call.
+        self.complete = self.current_depth > self.target_depth;
+    }
+
+    fn book_move(&mut self, model: &impl GameModel<GameMove = Move>) -> Option<Move> {
+        let (book, rng) = self.book.as_mut()?;
+        let index = book.choose(model.zobrist_key(), rng)?;
+        model.legal_moves().get(index).copied()
+    }
+
+    fn probe_endgame(&mut self, model: &impl GameModel<GameMove = Move>) -> Option<Outcome> {
+        let outcome = self.endgame.as_ref()?.probe(model.zobrist_key())?;
+        self.stats.endgame_hits += 1;
+        Some(outcome)
+    }
+
+    fn set_best(&mut self, result: AlphaBetaResult<Move>) {
+        if let Some(shared) = &self.shared {
+            *shared.lock().unwrap() = Some(result.clone());
         }
+        self.best_so_far = Some(result);
     }
 
-    fn search_internal(
+    // Stops for now, keeping the state so a later call can carry on where this one left off.
+    fn give_up(&mut self, segment_start: Instant) {
+        self.depth_time += segment_start.elapsed();
+        if let Some(observer) = self.observer.as_mut() {
+            observer.on_timeout(self.current_depth, &self.stats);
+        }
+    }
+
+    fn search_internal<Model>(
         &mut self,
-        model: &mut impl GameModel<GameMove = Move>,
-        start: SystemTime,
-        budget: Duration,
+        model: &mut Model,
+        limits: &SearchLimits,
         depth: usize,
-    ) -> Result<i32, bool> {
+    ) -> Result<i32, bool>
+    where
+        Model: GameModel<GameMove = Move>,
+        Eval: Evaluator<Model>,
+    {
         if depth == self.current_depth {
-            return Ok(model.score());
-        }
-        if let Some(score) = self.tt.score(model, self.current_depth - depth) {
-            return Ok(score);
+            self.stats.nodes += 1;
+            self.stats.leaf_evaluations += 1;
+            if let Some(outcome) = self.probe_endgame(model) {
+                return Ok(outcome.score_at(depth));
+            }
+            let (alpha, beta) = self.state[depth - 1].child_window();
+            return Ok(quiescence(model, &self.evaluator, depth, alpha, beta));
         }
+        let remaining = self.current_depth - depth;
 
         // If we don't already have a state object, generate one.
         if self.state.len() <= depth {
-            let moves = self.generate_moves(model);
+            self.stats.nodes += 1;
+            if let Some(outcome) = model.outcome() {
+                self.stats.leaf_evaluations += 1;
+                return Ok(outcome.score_at(depth));
+            }
+            if let Some(outcome) = self.probe_endgame(model) {
+                self.stats.leaf_evaluations += 1;
+                return Ok(outcome.score_at(depth));
+            }
+            let (alpha, beta) = self.state[depth - 1].child_window();
+            let entry = self.tt.probe(model.zobrist_key());
+            match entry {
+                Some(_) => self.stats.tt_hits += 1,
+                None => self.stats.tt_misses += 1,
+            }
+            if let Some(score) = entry.and_then(|entry| entry.cutoff(remaining, depth, alpha, beta))
+            {
+                self.stats.tt_cutoffs += 1;
+                return Ok(score);
+            }
+
+            let moves = self.generate_moves(model, entry, depth);
             if moves.is_empty() {
-                return Ok(model.score());
+                self.stats.leaf_evaluations += 1;
+                return Ok(self.evaluator.evaluate(model));
             }
 
-            let new_state = AlphaBetaState::new(
-                moves,
-                -self.state[depth - 1].beta,
-                -self.state[depth - 1].alpha,
-            );
-            self.state.push(new_state);
+            self.state.push(AlphaBetaState::new(moves, alpha, beta));
         }
 
         while !self.state[depth].moves.is_empty() {
-            // If we've expended our time budget, then exit.
-            if SystemTime::now().duration_since(start).unwrap() > budget {
-                println!("Budget exceeded!");
+            // If we've run out of time or nodes, or been told to stop, then exit.
+            if limits.exceeded(self.stats.nodes) {
                 return Err(false);
             }
 
-            let m = self.state[depth].moves.pop().unwrap();
+            let (index, m) = self.state[depth].moves.pop().unwrap();
 
             // Apply the move and get the score.
             model.apply(&m);
-            let score = self.search_internal(model, start, budget, depth + 1);
+            let score = self.search_internal(model, limits, depth + 1);
             model.undo(&m);
 
             match score {
                 // If we successfully found the score for this move, then move on to the next move.
                 Ok(score) => {
+                    let score = -score;
+                    let state = &mut self.state[depth];
+                    let floor = state.floor();
+                    // The move beat the null window, so search it again to find out by how much.
+                    if state.searched > 0 && !state.re_search && score > floor && score < state.beta
+                    {
+                        state.re_search = true;
+                        state.moves.push((index, m));
+                        continue;
+                    }
+                    if state.margin > 0 && score > floor && score < state.beta {
+                        self.root_scores.push((m, score));
+                    }
+                    let state = &mut self.state[depth];
+                    state.re_search = false;
+                    let searched = state.searched;
+                    state.searched += 1;
                     if score >= self.state[depth].beta {
+                        self.stats.add_beta_cutoff(searched);
+                        self.ordering.record_cutoff(m, depth, remaining);
                         let beta = self.state[depth].beta;
+                        self.tt.store(
+                            model.zobrist_key(),
+                            remaining,
+                            depth,
+                            beta,
+                            Bound::Lower,
+                            Some(index),
+                        );
                         self.state.pop();
+                        if depth == 0 {
+                            self.root_move = Some(m);
+                        }
                         return Ok(beta);
                     }
                     if score > self.state[depth].alpha {
                         self.state[depth].alpha = score;
-                        self.state[depth].best_move = Some(m);
+                        self.state[depth].best_move = Some((index, m));
+                        if depth == 0 {
+                            if let Some(observer) = self.observer.as_mut() {
+                                observer.on_new_best(self.current_depth, &m, score);
+                            }
+                        }
                     }
                 }
 
                 // If we timed out, then add the move back to be scored and push the error down.
                 Err(v) => {
-                    self.state[depth].moves.push(m);
+                    self.state[depth].moves.push((index, m));
                     return Err(v);
                 }
             }
         }
 
-        self.tt
-            .add_val(model, self.current_depth - depth, self.state[depth].alpha);
-        if let Some(m) = self.state[depth].best_move {
-            self.pvs.add_val(model, m);
+        let state = self.state.pop().unwrap();
+        let bound = match state.best_move {
+            Some(_) => Bound::Exact,
+            None => Bound::Upper,
+        };
+        self.tt.store(
+            model.zobrist_key(),
+            remaining,
+            depth,
+            state.alpha,
+            bound,
+            state.best_move.map(|(index, _)| index),
+        );
+        if depth == 0 {
+            self.root_move = state.best_move.map(|(_, m)| m);
         }
-        let alpha = self.state[depth].alpha;
-        self.state.pop();
-        Ok(alpha)
+        Ok(state.alpha)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IllegalMove<Move>(pub Move);
+
+impl<Move: Debug> Display for IllegalMove<Move> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "illegal move: {:?}", self.0)
     }
 }
 
-pub trait GameModel: Hash + Sized {
-    type GameMove: PartialEq + Copy;
+impl<Move: Debug> std::error::Error for IllegalMove<Move> {}
+
+pub trait GameModel: ZobristKey + Sized {
+    type GameMove: PartialEq + Copy + Hash;
     fn legal_moves(&self) -> Vec<Self::GameMove>;
+    // The search only applies moves from `legal_moves`, so this may assume the move is legal.
     fn apply(&mut self, m: &Self::GameMove);
     fn undo(&mut self, m: &Self::GameMove);
 
-    // From the perspective of the player who just moved.
-    fn score(&self) -> i32;
-
-    fn search(&mut self, depth: usize) -> Option<Self::GameMove> {
-        let mut tt = TranspositionTable::default();
-        let mut pvs = PVSTable {
-            table: HashMap::new(),
-        };
-        let legal_moves = self.legal_moves();
-
-        // Seeding pvs
-        for i in 1..depth {
-            let mut best = -i32::MAX;
-            for m in legal_moves.iter() {
-                self.apply(m);
-                let score = -search_internal(self, i, &mut tt, &mut pvs, best, i32::MAX);
-                if score > best {
-                    best = score;
-                }
-                self.undo(m);
-            }
+    // Applies a move that came from outside the search, such as player input.
+    fn try_apply(&mut self, m: &Self::GameMove) -> Result<(), IllegalMove<Self::GameMove>> {
+        if self.legal_moves().contains(m) {
+            self.apply(m);
+            Ok(())
+        } else {
+            Err(IllegalMove(*m))
         }
-
-        let mut move_scores = Vec::new();
-        let mut best = -i32::MAX;
-        let mut best_so_far = None;
-        for m in legal_moves {
-            self.apply(&m);
-            let score = -search_internal(self, depth, &mut tt, &mut pvs, best, i32::MAX);
-            move_scores.push((m.clone(), score));
-            if score > best {
-                best = score;
-                best_so_far = Some(m);
-            }
-            self.undo(&m);
-        }
-        best_so_far
-        // let b: Vec<Self::GameMove> = move_scores
-        //     .into_iter()
-        //     .filter_map(|(m, s)| if s == best { Some(m) } else { None })
-        //     .collect();
-        // println!("expected score: {:?}", best);
-        // b.choose(&mut rand::thread_rng()).cloned()
     }
-}
 
-// Returns the best score that the active player can get.
-fn search_internal<Model: GameModel>(
-    model: &mut Model,
-    depth: usize,
-    tt: &mut TranspositionTable,
-    pvs: &mut PVSTable<Model::GameMove>,
-    mut alpha: i32,
-    beta: i32,
-) -> i32 {
-    if depth == 0 {
-        return model.score();
+    // Captures, threats and other moves that can swing the score, which the search keeps playing past
+    // its horizon until the position is quiet. Each should also be in `legal_moves`.
+    fn noisy_moves(&self) -> Vec<Self::GameMove> {
+        Vec::new()
     }
-    let mut moves = model.legal_moves();
-    if moves.is_empty() {
-        return model.score();
+
+    // The move MCTS rollouts play with `Rollout::Model`. None leaves the choice to chance.
+    fn rollout_move(&self) -> Option<Self::GameMove> {
+        None
     }
-    match tt.score(model, depth) {
-        Some(score) => {
-            return score;
-        }
-        _ => {}
+
+    // From the perspective of the player to move.
+    fn score(&self) -> i32;
+
+    // The result if the game is over, for the player to move, such as `Outcome::Loss(0)` once the opponent has won.
+    fn outcome(&self) -> Option<Outcome> {
+        None
     }
 
-    pvs.sort(model, &mut moves);
+    // Searches `depth` plies past each of the moves and returns the best move, blocking until it's
+    // done. This runs the same search as `AlphaBetaSearch`, which can also be stopped and resumed,
+    // and whose depth counts the move itself.
+    fn search(&mut self, depth: usize) -> Option<Self::GameMove> {
+        let search = AlphaBetaSearch::start_search(self, depth + 1, SearchLimits::default());
+        search.best_so_far.map(|result| result.m)
+    }
 
-    let mut best_move = None;
-    for m in moves.iter() {
-        model.apply(m);
-        let score = -search_internal(model, depth - 1, tt, pvs, -beta, -alpha);
-        if score >= beta {
-            model.undo(m);
-            return beta;
+    // Searches to the end of the game, so the outcome is exact. Only suits small games that implement `outcome`.
+    fn solve(&mut self) -> (Option<Self::GameMove>, Outcome) {
+        if let Some(outcome) = self.outcome() {
+            return (None, outcome);
         }
-        if score > alpha {
-            alpha = score;
-            best_move = Some(*m);
+        let mut search = AlphaBetaSearch::new(SOLVE_DEPTH);
+        // Iterative deepening would only repeat the search at every depth on the way to the end.
+        search.current_depth = SOLVE_DEPTH;
+        search.continue_search(self, SearchLimits::default());
+        match search.best_so_far {
+            Some(result) => (Some(result.m), Outcome::from_exact_score(result.score)),
+            None => (None, Outcome::Heuristic(self.score())),
         }
-        model.undo(m);
-    }
-    tt.add_val(model, depth, alpha);
-    if let Some(m) = best_move {
-        pvs.add_val(model, m);
     }
-    alpha
 }
@@ -0,0 +1,94 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+// When a search should give up. Anything left as None doesn't limit it.
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    // Searches no deeper than this, even if the search was started with a deeper target.
+    pub max_depth: Option<usize>,
+    // Stops once the search has visited this many nodes in total, across every call.
+    pub max_nodes: Option<u64>,
+    // Stops as soon as this passes, even partway through a depth.
+    pub deadline: Option<Instant>,
+    // Doesn't start another depth after this, since it would likely not finish before the deadline.
+    pub soft_deadline: Option<Instant>,
+    // Set by someone else, such as the UI when the player leaves the game, to stop the search.
+    pub stop: Option<Arc<AtomicBool>>,
+}
+
+impl SearchLimits {
+    pub fn with_stop(mut self, stop: Arc<AtomicBool>) -> SearchLimits {
+        self.stop = Some(stop);
+        self
+    }
+
+    pub fn stopped(&self) -> bool {
+        match &self.stop {
+            Some(stop) => stop.load(Ordering::Relaxed),
+            None => false,
+        }
+    }
+
+    // Whether the search has to stop now, wherever it is.
+    pub fn exceeded(&self, nodes: u64) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+            || self.max_nodes.is_some_and(|max_nodes| nodes >= max_nodes)
+            || self.stopped()
+    }
+
+    // Whether there's still time to start searching another depth.
+    pub fn can_start_depth(&self, nodes: u64) -> bool {
+        !self.exceeded(nodes)
+            && self
+                .soft_deadline
+                .is_none_or(|soft_deadline| Instant::now() < soft_deadline)
+    }
+}
+
+// A plain time budget, starting now.
+impl From<Duration> for SearchLimits {
+    fn from(budget: Duration) -> Self {
+        SearchLimits {
+            deadline: Instant::now().checked_add(budget),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{atomic::AtomicBool, Arc},
+        time::{Duration, Instant},
+    };
+
+    use super::SearchLimits;
+
+    #[test]
+    fn limits() {
+        assert!(!SearchLimits::default().exceeded(u64::MAX));
+        assert!(SearchLimits::from(Duration::ZERO).exceeded(0));
+        assert!(!SearchLimits::from(Duration::from_secs(60)).exceeded(0));
+
+        let limits = SearchLimits {
+            max_nodes: Some(10),
+            soft_deadline: Some(Instant::now()),
+            ..Default::default()
+        };
+        assert!(!limits.exceeded(9));
+        assert!(limits.exceeded(10));
+        assert!(!limits.can_start_depth(0));
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let limits = SearchLimits::default().with_stop(stop.clone());
+        assert!(limits.can_start_depth(0));
+        stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        assert!(limits.exceeded(0));
+    }
+}
@@ -0,0 +1,320 @@
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{evaluate, Evaluator, GameModel, ModelScore, MAX_PLIES};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rollout {
+    // Plays uniformly random legal moves.
+    Random,
+    // Plays `GameModel::rollout_move`, falling back to a random move where it returns None.
+    Model,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MctsConfig {
+    // The UCT exploration constant. Higher values spread visits over more moves.
+    pub exploration: f64,
+    pub rollout: Rollout,
+    // Rollouts that haven't reached the end of the game by now are scored by the search's evaluator.
+    pub max_rollout_plies: usize,
+    pub seed: u64,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            exploration: std::f64::consts::SQRT_2,
+            rollout: Rollout::Random,
+            max_rollout_plies: MAX_PLIES as usize,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MctsBudget {
+    Time(Duration),
+    Iterations(usize),
+}
+
+// How one root move has done so far.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveStats<Move> {
+    pub m: Move,
+    pub visits: u32,
+    // The average result for the player making the move, from 0 for a loss to 1 for a win.
+    pub value: f64,
+}
+
+struct Node<Move> {
+    m: Option<Move>,
+    key: u64,
+    children: Vec<usize>,
+    untried: Vec<Move>,
+    visits: u32,
+    // Summed results for the player who made `m`.
+    wins: f64,
+}
+
+impl<Move> Node<Move> {
+    fn new(m: Option<Move>, model: &impl GameModel<GameMove = Move>) -> Node<Move> {
+        Node {
+            m,
+            key: model.zobrist_key(),
+            children: Vec::new(),
+            untried: match model.outcome() {
+                Some(_) => Vec::new(),
+                None => model.legal_moves(),
+            },
+            visits: 0,
+            wins: 0.,
+        }
+    }
+}
+
+// The result of a finished or abandoned game for the player to move.
+fn result<Model: GameModel>(model: &Model, evaluator: &impl Evaluator<Model>) -> f64 {
+    match evaluate(model, evaluator, 0).signum() {
+        1 => 1.,
+        -1 => 0.,
+        _ => 0.5,
+    }
+}
+
+// Plays the game out from `model` and puts it back, scored for the player to move at the start.
+pub(crate) fn rollout<Model: GameModel>(
+    model: &mut Model,
+    config: &MctsConfig,
+    evaluator: &impl Evaluator<Model>,
+    rng: &mut StdRng,
+) -> f64 {
+    let mut played = Vec::new();
+    while played.len() < config.max_rollout_plies && model.outcome().is_none() {
+        let model_move = match config.rollout {
+            Rollout::Model => model.rollout_move(),
+            Rollout::Random => None,
+        };
+        let m = match model_move {
+            Some(m) => m,
+            None => match model.legal_moves().choose(rng) {
+                Some(&m) => m,
+                None => break,
+            },
+        };
+        model.apply(&m);
+        played.push(m);
+    }
+    let value = result(model, evaluator);
+    for m in played.iter().rev() {
+        model.undo(m);
+    }
+    // Flip back to the player to move where the rollout started.
+    match played.len() % 2 {
+        0 => value,
+        _ => 1. - value,
+    }
+}
+
+// Monte Carlo tree search with UCT selection. The tree is kept between searches, so searching again
+// after a move or two reuses whatever was already learned about the new position.
+pub struct MctsSearch<Move, Eval = ModelScore> {
+    pub config: MctsConfig,
+    evaluator: Eval,
+    nodes: Vec<Node<Move>>,
+    rng: StdRng,
+}
+
+impl<Move: PartialEq + Copy> MctsSearch<Move> {
+    pub fn new(config: MctsConfig) -> MctsSearch<Move> {
+        MctsSearch::with_evaluator(config, ModelScore)
+    }
+}
+
+impl<Move: PartialEq + Copy, Eval> MctsSearch<Move, Eval> {
+    // Scores rollouts cut short by `max_rollout_plies` with `evaluator` instead of the model's own
+    // `score`.
+    pub fn with_evaluator(config: MctsConfig, evaluator: Eval) -> MctsSearch<Move, Eval> {
+        MctsSearch {
+            config,
+            evaluator,
+            nodes: Vec::new(),
+            rng: StdRng::seed_from_u64(config.seed),
+        }
+    }
+
+    pub fn root_visits(&self) -> u32 {
+        self.nodes.first().map_or(0, |root| root.visits)
+    }
+
+    pub fn move_stats(&self) -> Vec<MoveStats<Move>> {
+        match self.nodes.first() {
+            Some(root) => root
+                .children
+                .iter()
+                .map(|&child| {
+                    let node = &self.nodes[child];
+                    MoveStats {
+                        m: node.m.unwrap(),
+                        visits: node.visits,
+                        value: node.wins / node.visits.max(1) as f64,
+                    }
+                })
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    // The most visited root move, which is more reliable than the one with the best average.
+    pub fn best_move(&self) -> Option<Move> {
+        self.move_stats()
+            .into_iter()
+            .max_by_key(|stats| stats.visits)
+            .map(|stats| stats.m)
+    }
+
+    pub fn search<Model>(&mut self, model: &mut Model, budget: MctsBudget) -> Option<Move>
+    where
+        Model: GameModel<GameMove = Move>,
+        Eval: Evaluator<Model>,
+    {
+        self.search_until(model, budget, &AtomicBool::new(false))
+    }
+
+    // Like `search`, but also stops once someone else sets `stop`.
+    pub fn search_until<Model>(
+        &mut self,
+        model: &mut Model,
+        budget: MctsBudget,
+        stop: &AtomicBool,
+    ) -> Option<Move>
+    where
+        Model: GameModel<GameMove = Move>,
+        Eval: Evaluator<Model>,
+    {
+        self.reroot(model);
+        let start = Instant::now();
+        let mut iterations = 0;
+        loop {
+            let done = match budget {
+                MctsBudget::Time(time) => start.elapsed() > time,
+                MctsBudget::Iterations(limit) => iterations >= limit,
+            };
+            if done || stop.load(Ordering::Relaxed) {
+                break;
+            }
+            self.iterate(model);
+            iterations += 1;
+        }
+        self.best_move()
+    }
+
+    // Keeps the subtree for the model's position if it's within two plies of the old root, and
+    // starts a new tree otherwise.
+    fn reroot(&mut self, model: &impl GameModel<GameMove = Move>) {
+        let key = model.zobrist_key();
+        let mut frontier = vec![0];
+        for _ in 0..3 {
+            if let Some(&found) = frontier
+                .iter()
+                .find(|&&node| self.nodes.get(node).map(|node| node.key) == Some(key))
+            {
+                if found != 0 {
+                    self.nodes = self.take_subtree(found);
+                }
+                return;
+            }
+            frontier = frontier
+                .iter()
+                .filter_map(|&node| self.nodes.get(node))
+                .flat_map(|node| node.children.iter().copied())
+                .collect();
+        }
+        self.nodes = vec![Node::new(None, model)];
+    }
+
+    // Copies the subtree under `root` into a new arena with `root` first.
+    fn take_subtree(&mut self, root: usize) -> Vec<Node<Move>> {
+        let mut old = std::mem::take(&mut self.nodes)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        let mut nodes = Vec::new();
+        // Nodes are stored in the order they're queued, so children get the next free indices.
+        let mut queue = VecDeque::from([root]);
+        let mut queued = 1;
+        while let Some(index) = queue.pop_front() {
+            let mut node = old[index].take().unwrap();
+            queue.extend(node.children.iter().copied());
+            node.children = (queued..queued + node.children.len()).collect();
+            queued += node.children.len();
+            nodes.push(node);
+        }
+        nodes
+    }
+
+    fn iterate<Model>(&mut self, model: &mut Model)
+    where
+        Model: GameModel<GameMove = Move>,
+        Eval: Evaluator<Model>,
+    {
+        let mut path = vec![0];
+        let mut applied = Vec::new();
+
+        // Selection: follow the UCT choice down to a node with moves left to try.
+        let mut node = 0;
+        while self.nodes[node].untried.is_empty() && !self.nodes[node].children.is_empty() {
+            node = self.select(node);
+            let m = self.nodes[node].m.unwrap();
+            model.apply(&m);
+            applied.push(m);
+            path.push(node);
+        }
+
+        // Expansion: add one untried move.
+        if !self.nodes[node].untried.is_empty() {
+            let untried = &mut self.nodes[node].untried;
+            let m = untried.swap_remove(self.rng.gen_range(0..untried.len()));
+            model.apply(&m);
+            applied.push(m);
+            let child = self.nodes.len();
+            self.nodes.push(Node::new(Some(m), model));
+            self.nodes[node].children.push(child);
+            path.push(child);
+        }
+
+        // Simulation, scored for the player to move at the new node.
+        let mut value = rollout(model, &self.config, &self.evaluator, &mut self.rng);
+
+        // Backpropagation: each node's wins belong to the player who moved into it.
+        for &index in path.iter().rev() {
+            value = 1. - value;
+            let node = &mut self.nodes[index];
+            node.visits += 1;
+            node.wins += value;
+        }
+        for m in applied.iter().rev() {
+            model.undo(m);
+        }
+    }
+
+    fn select(&self, parent: usize) -> usize {
+        let log_visits = (self.nodes[parent].visits.max(1) as f64).ln();
+        let uct = |child: usize| {
+            let node = &self.nodes[child];
+            let visits = node.visits.max(1) as f64;
+            node.wins / visits + self.config.exploration * (log_visits / visits).sqrt()
+        };
+        self.nodes[parent]
+            .children
+            .iter()
+            .copied()
+            .max_by(|&a, &b| uct(a).total_cmp(&uct(b)))
+            .unwrap()
+    }
+}
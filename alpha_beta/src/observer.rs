@@ -0,0 +1,34 @@
+use crate::{AlphaBetaResult, SearchStats};
+
+// Hooks for following a search without it writing to stdout. Every method defaults to doing nothing.
+pub trait SearchObserver<Move>: Send + Sync {
+    fn on_depth_complete(&mut self, _result: &AlphaBetaResult<Move>, _stats: &SearchStats) {}
+
+    // A root move beat every move searched before it at `depth`, which may not be complete yet.
+    fn on_new_best(&mut self, _depth: usize, _m: &Move, _score: i32) {}
+
+    fn on_timeout(&mut self, _depth: usize, _stats: &SearchStats) {}
+}
+
+impl<Move, F> SearchObserver<Move> for F
+where
+    F: FnMut(&AlphaBetaResult<Move>, &SearchStats) + Send + Sync,
+{
+    fn on_depth_complete(&mut self, result: &AlphaBetaResult<Move>, stats: &SearchStats) {
+        self(result, stats)
+    }
+}
+
+// Prints progress the way the search used to, for interactive runs.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct PrintObserver;
+
+impl<Move: std::fmt::Debug> SearchObserver<Move> for PrintObserver {
+    fn on_depth_complete(&mut self, result: &AlphaBetaResult<Move>, _stats: &SearchStats) {
+        println!("Solved depth: {}, pv: {:?}", result.depth, result.pv);
+    }
+
+    fn on_timeout(&mut self, depth: usize, _stats: &SearchStats) {
+        println!("Budget exceeded at depth {}!", depth);
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use crate::{Entry, GameModel, MoveIndex};
+
+// Moves that caused a cutoff at the same ply elsewhere in the tree, and how often each move
+// has caused one anywhere. Either tends to cut off again, so they're tried early.
+pub struct MoveOrdering<Move> {
+    killers: Vec<[Option<Move>; 2]>,
+    history: HashMap<u64, u64>,
+}
+
+impl<Move> Default for MoveOrdering<Move> {
+    fn default() -> Self {
+        MoveOrdering {
+            killers: Vec::new(),
+            history: HashMap::new(),
+        }
+    }
+}
+
+fn move_hash<Move: Hash>(m: &Move) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    m.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<Move: PartialEq + Copy + Hash> MoveOrdering<Move> {
+    pub fn history(&self, m: &Move) -> u64 {
+        self.history.get(&move_hash(m)).copied().unwrap_or(0)
+    }
+
+    pub fn killers(&self, ply: usize) -> [Option<Move>; 2] {
+        self.killers.get(ply).copied().unwrap_or([None, None])
+    }
+
+    // `remaining` is the depth left below the node, since cutoffs near the root save the most work.
+    pub fn record_cutoff(&mut self, m: Move, ply: usize, remaining: usize) {
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None, None]);
+        }
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(m) {
+            killers[1] = killers[0];
+            killers[0] = Some(m);
+        }
+        *self.history.entry(move_hash(&m)).or_insert(0) += (remaining * remaining) as u64;
+    }
+
+    // Legal moves paired with their index: the table's best move (if any), then the killers, then the
+    // rest by history. Ties keep the model's order.
+    pub fn order<Model: GameModel<GameMove = Move>>(
+        &self,
+        model: &Model,
+        entry: Option<Entry>,
+        ply: usize,
+    ) -> Vec<(MoveIndex, Move)> {
+        let best = entry.and_then(|entry| entry.best_move());
+        let killers = self.killers(ply);
+        let mut moves = model
+            .legal_moves()
+            .into_iter()
+            .enumerate()
+            .map(|(index, m)| {
+                let rank = if Some(index) == best {
+                    u64::MAX
+                } else if killers[0] == Some(m) {
+                    u64::MAX - 1
+                } else if killers[1] == Some(m) {
+                    u64::MAX - 2
+                } else {
+                    self.history(&m)
+                };
+                (rank, (index, m))
+            })
+            .collect::<Vec<_>>();
+        moves.sort_by_key(|&(rank, _)| Reverse(rank));
+        moves.into_iter().map(|(_, m)| m).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MoveOrdering;
+
+    #[test]
+    fn killers_and_history() {
+        let mut ordering = MoveOrdering::default();
+        ordering.record_cutoff(3, 1, 2);
+        ordering.record_cutoff(5, 1, 4);
+        ordering.record_cutoff(5, 1, 1);
+        assert_eq!(ordering.killers(1), [Some(5), Some(3)]);
+        assert_eq!(ordering.killers(0), [None, None]);
+        assert_eq!(ordering.history(&5), 17);
+        assert_eq!(ordering.history(&3), 4);
+        assert_eq!(ordering.history(&7), 0);
+    }
+}
@@ -0,0 +1,106 @@
+// Scores at or beyond this are wins, shortened by one for every ply until the game ends.
+pub const WIN_SCORE: i32 = 1_000_000;
+// Wins and losses can be at most this many plies away, which keeps heuristic scores below the win range.
+pub const MAX_PLIES: i32 = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    // The player to move wins, with the game ending after this many plies.
+    Win(usize),
+    Loss(usize),
+    Draw,
+    // The search stopped before the game ended, so this is the evaluation at its horizon.
+    Heuristic(i32),
+}
+
+impl Outcome {
+    pub fn from_score(score: i32) -> Outcome {
+        // Anything beyond a win, such as the search's infinite window, is left as it is.
+        if (WIN_SCORE - MAX_PLIES..=WIN_SCORE).contains(&score) {
+            Outcome::Win((WIN_SCORE - score) as usize)
+        } else if (-WIN_SCORE..=-(WIN_SCORE - MAX_PLIES)).contains(&score) {
+            Outcome::Loss((WIN_SCORE + score) as usize)
+        } else {
+            Outcome::Heuristic(score)
+        }
+    }
+
+    // For scores from a search that only stopped at the end of the game, where 0 means a draw.
+    pub fn from_exact_score(score: i32) -> Outcome {
+        match Outcome::from_score(score) {
+            Outcome::Heuristic(_) => Outcome::Draw,
+            outcome => outcome,
+        }
+    }
+
+    pub fn score(&self) -> i32 {
+        match *self {
+            Outcome::Win(plies) => WIN_SCORE - plies as i32,
+            Outcome::Loss(plies) => -(WIN_SCORE - plies as i32),
+            Outcome::Draw => 0,
+            Outcome::Heuristic(score) => score,
+        }
+    }
+
+    // The score of a game that ended `ply` plies into the search.
+    pub fn score_at(&self, ply: usize) -> i32 {
+        match *self {
+            Outcome::Win(plies) => Outcome::Win(plies + ply).score(),
+            Outcome::Loss(plies) => Outcome::Loss(plies + ply).score(),
+            outcome => outcome.score(),
+        }
+    }
+
+    pub fn is_decided(&self) -> bool {
+        !matches!(self, Outcome::Heuristic(_))
+    }
+}
+
+// The table stores win distances from the position itself, so they stay valid wherever it is reached.
+pub fn to_table(score: i32, ply: usize) -> i32 {
+    match Outcome::from_score(score) {
+        Outcome::Win(_) => score + ply as i32,
+        Outcome::Loss(_) => score - ply as i32,
+        _ => score,
+    }
+}
+
+pub fn from_table(score: i32, ply: usize) -> i32 {
+    match Outcome::from_score(score) {
+        Outcome::Win(_) => score - ply as i32,
+        Outcome::Loss(_) => score + ply as i32,
+        _ => score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_table, to_table, Outcome};
+
+    #[test]
+    fn scores() {
+        for outcome in [
+            Outcome::Win(3),
+            Outcome::Loss(0),
+            Outcome::Heuristic(-400),
+            Outcome::Heuristic(0),
+        ] {
+            assert_eq!(Outcome::from_score(outcome.score()), outcome);
+        }
+        assert_eq!(Outcome::from_exact_score(0), Outcome::Draw);
+        assert!(Outcome::Win(9).score() < Outcome::Win(1).score());
+        assert!(Outcome::Loss(1).score() < Outcome::Loss(9).score());
+        assert!(Outcome::Heuristic(10000).score() < Outcome::Win(999).score());
+        assert_eq!(Outcome::Loss(0).score_at(4), Outcome::Loss(4).score());
+        assert_eq!(to_table(-i32::MAX, 3), -i32::MAX);
+    }
+
+    #[test]
+    fn table_scores() {
+        // A win 5 plies from the root, stored at ply 2 and read back at ply 4.
+        let stored = to_table(Outcome::Win(5).score(), 2);
+        assert_eq!(Outcome::from_score(stored), Outcome::Win(3));
+        assert_eq!(Outcome::from_score(from_table(stored, 4)), Outcome::Win(7));
+        assert_eq!(from_table(to_table(-50, 3), 3), -50);
+    }
+}
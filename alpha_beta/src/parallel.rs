@@ -0,0 +1,89 @@
+use std::{
+    hash::Hash,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use crate::{AlphaBetaSearch, Evaluator, GameModel, SearchLimits};
+
+// Lazy SMP: helper threads search copies of the model over the shared transposition table, and the
+// entries they leave behind let the main search skip work or order its moves better.
+impl<Move: PartialEq + Copy + Hash + Send> AlphaBetaSearch<Move> {
+    pub fn start_parallel_search<Model>(
+        model: &mut Model,
+        target_depth: usize,
+        limits: impl Into<SearchLimits>,
+        threads: usize,
+    ) -> AlphaBetaSearch<Move>
+    where
+        Model: GameModel<GameMove = Move> + Clone + Send,
+    {
+        let mut search_data = AlphaBetaSearch::new(target_depth);
+        search_data.continue_parallel_search(model, limits, threads);
+        search_data
+    }
+}
+
+impl<Move: PartialEq + Copy + Hash + Send, Eval: Clone + Send> AlphaBetaSearch<Move, Eval> {
+    // Runs `threads - 1` helpers alongside this search until it completes or runs out of budget.
+    pub fn continue_parallel_search<Model>(
+        &mut self,
+        model: &mut Model,
+        limits: impl Into<SearchLimits>,
+        threads: usize,
+    ) where
+        Model: GameModel<GameMove = Move> + Clone + Send,
+        Eval: Evaluator<Model>,
+    {
+        if self.complete {
+            return;
+        }
+        let limits = limits.into();
+        // Helpers stop when the main search does, whatever the reason.
+        let stop = Arc::new(AtomicBool::new(false));
+        let results = thread::scope(|scope| {
+            let helpers = (1..threads)
+                .map(|i| {
+                    // Half of the helpers start a depth ahead, so threads spread over more of the tree.
+                    let mut helper = AlphaBetaSearch::with_table(
+                        self.target_depth,
+                        self.config,
+                        self.tt.clone(),
+                        self.evaluator.clone(),
+                    );
+                    helper.current_depth = self.current_depth + i % 2;
+                    helper.endgame = self.endgame.clone();
+                    let helper_limits = limits.clone().with_stop(stop.clone());
+                    let mut helper_model = model.clone();
+                    scope.spawn(move || {
+                        helper.continue_search(&mut helper_model, helper_limits);
+                        helper.best_so_far
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            self.continue_search(model, limits.clone());
+            stop.store(true, Ordering::Relaxed);
+            helpers
+                .into_iter()
+                .filter_map(|helper| helper.join().ok().flatten())
+                .collect::<Vec<_>>()
+        });
+
+        // A helper may have finished a depth the main search hasn't, in which case skip ahead to it.
+        for result in results {
+            if result.depth >= self.current_depth {
+                self.current_depth = result.depth + 1;
+                self.window = self.config.aspiration(result.score);
+                self.state.clear();
+                self.set_best(result);
+            }
+        }
+        if self.current_depth > self.target_depth {
+            self.complete = true;
+        }
+    }
+}
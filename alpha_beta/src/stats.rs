@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DepthStats {
+    pub depth: usize,
+    pub nodes: u64,
+    pub time: Duration,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub nodes: u64,
+    pub leaf_evaluations: u64,
+    pub tt_hits: u64,
+    pub tt_misses: u64,
+    pub tt_cutoffs: u64,
+    // Positions scored from the endgame table.
+    pub endgame_hits: u64,
+    // Depths searched again because the score fell outside the aspiration window.
+    pub researches: u64,
+    // Beta cutoffs, indexed by the position of the cutting move in the ordered move list.
+    pub beta_cutoffs: Vec<u64>,
+    pub depths: Vec<DepthStats>,
+}
+
+impl SearchStats {
+    pub fn add_beta_cutoff(&mut self, move_index: usize) {
+        if self.beta_cutoffs.len() <= move_index {
+            self.beta_cutoffs.resize(move_index + 1, 0);
+        }
+        self.beta_cutoffs[move_index] += 1;
+    }
+
+    // The share of beta cutoffs caused by the first move searched, which is what move ordering aims to maximise.
+    pub fn first_move_cutoff_rate(&self) -> Option<f64> {
+        let total = self.beta_cutoffs.iter().sum::<u64>();
+        match total {
+            0 => None,
+            _ => Some(self.beta_cutoffs[0] as f64 / total as f64),
+        }
+    }
+
+    // How many more nodes the last completed depth took than the one before it.
+    pub fn effective_branching_factor(&self) -> Option<f64> {
+        match self.depths.as_slice() {
+            [.., previous, last] if previous.nodes > 0 => {
+                Some(last.nodes as f64 / previous.nodes as f64)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{DepthStats, SearchStats};
+
+    #[test]
+    fn derived_stats() {
+        let mut stats = SearchStats::default();
+        assert_eq!(stats.first_move_cutoff_rate(), None);
+        assert_eq!(stats.effective_branching_factor(), None);
+
+        stats.add_beta_cutoff(0);
+        stats.add_beta_cutoff(0);
+        stats.add_beta_cutoff(0);
+        stats.add_beta_cutoff(2);
+        assert_eq!(stats.beta_cutoffs, vec![3, 0, 1]);
+        assert_eq!(stats.first_move_cutoff_rate(), Some(0.75));
+
+        for (depth, nodes) in [(1, 7), (2, 20), (3, 80)] {
+            stats.depths.push(DepthStats {
+                depth,
+                nodes,
+                time: Duration::ZERO,
+            });
+        }
+        assert_eq!(stats.effective_branching_factor(), Some(4.));
+    }
+}
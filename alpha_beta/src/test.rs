@@ -1,243 +1,1253 @@
 use std::{
-    collections::{HashMap, HashSet},
-    hash::Hash,
+    collections::{hash_map::DefaultHasher, HashSet},
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    sync::{atomic::AtomicBool, Arc, Mutex},
+    time::{Duration, Instant},
 };
 
-use crate::GameModel;
-
-#[derive(Hash, Clone, Copy, Eq, PartialEq)]
-struct Position {
-    pub x: usize,
-    pub y: usize,
-}
-
-#[derive(Hash, Clone, Copy, Eq, PartialEq)]
-enum Direction {
-    N,
-    NE,
-    E,
-    SE,
-    S,
-    SW,
-    W,
-    NW,
-}
-
-impl Position {
-    fn next(&self, dir: Direction) -> Option<Position> {
-        match dir {
-            Direction::N => {
-                if self.y < 7 {
-                    Some(Position {
-                        x: self.x,
-                        y: self.y + 1,
-                    })
-                } else {
-                    None
-                }
-            }
-            Direction::NE => {
-                if self.y < 7 && self.x < 7 {
-                    Some(Position {
-                        x: self.x + 1,
-                        y: self.y + 1,
-                    })
-                } else {
-                    None
-                }
-            }
-            Direction::E => {
-                if self.x < 7 {
-                    Some(Position {
-                        x: self.x + 1,
-                        y: self.y,
-                    })
-                } else {
-                    None
-                }
-            }
-            Direction::SE => {
-                if self.y > 0 && self.x < 7 {
-                    Some(Position {
-                        x: self.x + 1,
-                        y: self.y - 1,
-                    })
-                } else {
-                    None
-                }
-            }
-            Direction::S => {
-                if self.y > 0 {
-                    Some(Position {
-                        x: self.x,
-                        y: self.y - 1,
-                    })
-                } else {
-                    None
-                }
-            }
-            Direction::SW => {
-                if self.y > 0 && self.x > 0 {
-                    Some(Position {
-                        x: self.x - 1,
-                        y: self.y - 1,
-                    })
-                } else {
-                    None
-                }
-            }
-            Direction::W => {
-                if self.x > 0 {
-                    Some(Position {
-                        x: self.x - 1,
-                        y: self.y,
-                    })
-                } else {
-                    None
-                }
-            }
-            Direction::NW => {
-                if self.y < 7 && self.x > 0 {
-                    Some(Position {
-                        x: self.x - 1,
-                        y: self.y + 1,
-                    })
-                } else {
-                    None
-                }
-            }
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    testing::{check_moves, perft, perft_checked, random_playouts, Inconsistency, ModelError},
+    AlphaBetaResult, AlphaBetaSearch, BookBuilder, BookMove, ChanceModel, DeterminizedConfig,
+    DeterminizedSearch, Difficulty, EndgameBuilder, Evaluator, ExpectimaxConfig, ExpectimaxSearch,
+    GameModel, HiddenInformation, IllegalMove, InformationSet, IsmctsSearch, MctsBudget,
+    MctsConfig, MctsHandle, MctsSearch, ModelScore, Noise, Outcome, SearchConfig, SearchHandle,
+    SearchLimits, SearchObserver, SearchStats, TranspositionTable, ZobristKey, ZobristTable,
+    DEFAULT_SEED, DEFAULT_TABLE_MB,
+};
+
+// Players take 1 to 3 stones in turn, and whoever takes the last stone wins.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Nim {
+    pub stones: usize,
+    pub active: usize,
+}
+
+impl Nim {
+    pub fn new(stones: usize) -> Nim {
+        Nim { stones, active: 0 }
+    }
+}
+
+impl ZobristKey for Nim {
+    fn zobrist_key(&self) -> u64 {
+        (self.stones as u64 * 2 + self.active as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+    }
+}
+
+impl GameModel for Nim {
+    type GameMove = usize;
+
+    fn legal_moves(&self) -> Vec<usize> {
+        (1..=3.min(self.stones)).collect()
+    }
+
+    fn apply(&mut self, m: &usize) {
+        self.stones -= m;
+        self.active = 1 - self.active;
+    }
+
+    fn undo(&mut self, m: &usize) {
+        self.stones += m;
+        self.active = 1 - self.active;
+    }
+
+    fn score(&self) -> i32 {
+        match self.stones {
+            0 => -100,
+            _ => 0,
         }
     }
 
-    pub fn line(&self, dir: Direction) -> Vec<Position> {
-        let mut line = Vec::new();
-        let mut next = self.next(dir);
-        while let Some(next_pos) = next {
-            line.push(next_pos);
-            next = next_pos.next(dir);
+    fn outcome(&self) -> Option<Outcome> {
+        match self.stones {
+            0 => Some(Outcome::Loss(0)),
+            _ => None,
         }
-        line
     }
+}
 
-    pub fn surround(&self) -> Vec<Position> {
-        let mut surround = Vec::new();
-        for e in [
-            Direction::N,
-            Direction::NE,
-            Direction::E,
-            Direction::SE,
-            Direction::S,
-            Direction::SW,
-            Direction::W,
-            Direction::NW,
-        ] {
-            if let Some(next) = self.next(e) {
-                surround.push(next);
-            }
+// A row of pieces that players capture in turn, taking the value of each. Either player may pass
+// instead, so grabbing a piece is only good if it doesn't hand the opponent a bigger one.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Exchange {
+    pub values: Vec<i32>,
+    pub taken: usize,
+    // Material won so far, for the player to move.
+    pub balance: i32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Play {
+    Capture,
+    Pass,
+}
+
+impl Exchange {
+    pub fn new(values: &[i32]) -> Exchange {
+        Exchange {
+            values: values.to_vec(),
+            taken: 0,
+            balance: 0,
         }
-        surround
     }
 }
 
-#[derive(Hash, Clone, Copy, Eq, PartialEq)]
-enum Player {
-    White,
-    Black,
+impl ZobristKey for Exchange {
+    // The game is the same for both players, so who is to move doesn't matter.
+    fn zobrist_key(&self) -> u64 {
+        ((self.taken as u64) << 32 | self.balance as u32 as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+    }
 }
 
-impl Player {
-    pub fn score(&self) -> i32 {
-        match self {
-            Player::White => 1,
-            Player::Black => -1,
+impl GameModel for Exchange {
+    type GameMove = Play;
+
+    fn legal_moves(&self) -> Vec<Play> {
+        let mut moves = self.noisy_moves();
+        moves.push(Play::Pass);
+        moves
+    }
+
+    fn noisy_moves(&self) -> Vec<Play> {
+        match self.taken < self.values.len() {
+            true => vec![Play::Capture],
+            false => vec![],
+        }
+    }
+
+    fn apply(&mut self, m: &Play) {
+        if *m == Play::Capture {
+            self.balance += self.values[self.taken];
+            self.taken += 1;
+        }
+        self.balance = -self.balance;
+    }
+
+    fn undo(&mut self, m: &Play) {
+        self.balance = -self.balance;
+        if *m == Play::Capture {
+            self.taken -= 1;
+            self.balance -= self.values[self.taken];
         }
     }
+
+    fn score(&self) -> i32 {
+        self.balance
+    }
 }
-#[derive(Hash, Clone, Copy, Eq, PartialEq)]
-enum Piece {
-    King,
-    Queen,
-    Rook,
+
+// Players race to `target`. Each turn they either step forward one square or gamble on a roll that
+// moves them 4 squares a third of the time and not at all otherwise.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Race {
+    pub totals: [u32; 2],
+    pub active: usize,
+    pub target: u32,
+    // The last player to move gambled, and the roll hasn't happened yet.
+    pub rolling: bool,
 }
 
-impl Piece {
-    pub fn score(&self) -> i32 {
-        match self {
-            Piece::King => 3,
-            Piece::Queen => 9,
-            Piece::Rook => 5,
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Step {
+    Safe,
+    Gamble,
+}
+
+impl Race {
+    pub fn new(totals: [u32; 2], target: u32) -> Race {
+        Race {
+            totals,
+            active: 0,
+            target,
+            rolling: false,
         }
     }
 }
-#[derive(Hash, Clone, Copy, Eq, PartialEq)]
-struct GamePiece(pub Player, pub Piece);
 
-impl GamePiece {
-    pub fn score(&self) -> i32 {
-        self.0.score() * self.1.score()
+impl ZobristKey for Race {
+    fn zobrist_key(&self) -> u64 {
+        let state = (self.totals[0] as u64) << 32
+            | (self.totals[1] as u64) << 2
+            | (self.active as u64) << 1
+            | self.rolling as u64;
+        state.wrapping_mul(0x9e37_79b9_7f4a_7c15)
     }
 }
 
-#[derive(Clone, PartialEq, Copy)]
-struct Move {
-    pub start: Position,
-    pub end: Position,
-    pub piece: GamePiece,
-    pub capture: Option<GamePiece>,
+impl GameModel for Race {
+    type GameMove = Step;
+
+    fn legal_moves(&self) -> Vec<Step> {
+        vec![Step::Safe, Step::Gamble]
+    }
+
+    fn apply(&mut self, m: &Step) {
+        match m {
+            Step::Safe => self.totals[self.active] += 1,
+            Step::Gamble => self.rolling = true,
+        }
+        self.active = 1 - self.active;
+    }
+
+    fn undo(&mut self, m: &Step) {
+        self.active = 1 - self.active;
+        match m {
+            Step::Safe => self.totals[self.active] -= 1,
+            Step::Gamble => self.rolling = false,
+        }
+    }
+
+    fn score(&self) -> i32 {
+        10 * (self.totals[self.active] as i32 - self.totals[1 - self.active] as i32)
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        match self.totals[1 - self.active] >= self.target {
+            true => Some(Outcome::Loss(0)),
+            false => None,
+        }
+    }
+}
+
+impl ChanceModel for Race {
+    type ChanceEvent = u32;
+
+    fn chance_events(&self) -> Vec<(u32, f64)> {
+        match self.rolling {
+            true => vec![(4, 1. / 3.), (0, 2. / 3.)],
+            false => Vec::new(),
+        }
+    }
+
+    fn apply_chance(&mut self, roll: &u32) {
+        self.totals[1 - self.active] += roll;
+        self.rolling = false;
+    }
+
+    fn undo_chance(&mut self, roll: &u32) {
+        self.totals[1 - self.active] -= roll;
+        self.rolling = true;
+    }
+}
+
+// Two tricks with a hand of two cards each out of six. The first player leads both with a card
+// face up, the second answers and the higher card takes the trick, with ties going to the leader.
+// Neither player sees the other's hand or the two cards nobody was dealt.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct HighCard {
+    pub hands: [Vec<u8>; 2],
+    // Every card played so far, in order.
+    pub played: Vec<u8>,
+}
+
+impl HighCard {
+    fn active(&self) -> usize {
+        self.played.len() % 2
+    }
+
+    fn tricks(&self) -> [i32; 2] {
+        let mut tricks = [0, 0];
+        for trick in self.played.chunks_exact(2) {
+            tricks[(trick[1] > trick[0]) as usize] += 1;
+        }
+        tricks
+    }
+}
+
+impl ZobristKey for HighCard {
+    fn zobrist_key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hands.hash(&mut hasher);
+        self.played.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl GameModel for HighCard {
+    type GameMove = u8;
+
+    fn legal_moves(&self) -> Vec<u8> {
+        self.hands[self.active()].clone()
+    }
+
+    fn apply(&mut self, card: &u8) {
+        let active = self.active();
+        self.hands[active].retain(|held| held != card);
+        self.played.push(*card);
+    }
+
+    fn undo(&mut self, card: &u8) {
+        self.played.pop();
+        let hand = &mut self.hands[self.active()];
+        hand.push(*card);
+        hand.sort();
+    }
+
+    fn score(&self) -> i32 {
+        let tricks = self.tricks();
+        10 * (tricks[self.active()] - tricks[1 - self.active()])
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        if self.played.len() < 4 {
+            return None;
+        }
+        Some(match self.score().signum() {
+            1 => Outcome::Win(0),
+            -1 => Outcome::Loss(0),
+            _ => Outcome::Draw,
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct HighCardView {
+    pub player: usize,
+    pub hand: Vec<u8>,
+    pub played: Vec<u8>,
+}
+
+impl HiddenInformation for HighCard {
+    type View = HighCardView;
+
+    fn view(&self) -> HighCardView {
+        let player = self.active();
+        HighCardView {
+            player,
+            hand: self.hands[player].clone(),
+            played: self.played.clone(),
+        }
+    }
+}
+
+impl InformationSet for HighCardView {
+    type Model = HighCard;
+
+    fn determinize(&self, rng: &mut impl Rng) -> HighCard {
+        let mut unseen = (1..=6)
+            .filter(|card| !self.hand.contains(card) && !self.played.contains(card))
+            .collect::<Vec<_>>();
+        unseen.shuffle(rng);
+        let mut hidden = unseen[..4 - self.played.len() - self.hand.len()].to_vec();
+        hidden.sort();
+        let mut hands = [hidden.clone(), hidden];
+        hands[self.player] = self.hand.clone();
+        HighCard {
+            hands,
+            played: self.played.clone(),
+        }
+    }
+}
+
+static LINES: [[usize; 3]; 8] = [
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [0, 3, 6],
+    [1, 4, 7],
+    [2, 5, 8],
+    [0, 4, 8],
+    [2, 4, 6],
+];
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TicTacToe {
+    pub cells: [Option<usize>; 9],
+    pub active: usize,
+    key: u64,
+    zobrist: ZobristTable,
 }
 
-struct BadChess {
-    pub board: HashMap<Position, GamePiece>,
-    pub active: Player,
+impl Default for TicTacToe {
+    fn default() -> Self {
+        TicTacToe {
+            cells: [None; 9],
+            active: 0,
+            key: 0,
+            zobrist: ZobristTable::new(DEFAULT_SEED, 18),
+        }
+    }
 }
 
-impl Hash for BadChess {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        for kv in self.board.iter() {
-            kv.hash(state);
+impl TicTacToe {
+    pub fn from_moves(moves: &[usize]) -> TicTacToe {
+        let mut model = TicTacToe::default();
+        for m in moves {
+            model.apply(m);
         }
-        self.active.hash(state);
+        model
     }
 }
 
-impl GameModel for BadChess {
-    type GameMove = Move;
+impl ZobristKey for TicTacToe {
+    fn zobrist_key(&self) -> u64 {
+        self.key
+    }
+}
+
+impl GameModel for TicTacToe {
+    type GameMove = usize;
+
+    fn legal_moves(&self) -> Vec<usize> {
+        match self.outcome() {
+            Some(_) => Vec::new(),
+            None => (0..9).filter(|&i| self.cells[i].is_none()).collect(),
+        }
+    }
 
-    fn legal_moves(&self) -> Vec<Self::GameMove> {
-        let moves = Vec::new();
-        for (_pos, gp) in self.board.iter().filter(|(_pos, gp)| gp.0 == self.active) {
-            match gp.1 {
-                Piece::King => todo!(),
-                Piece::Queen => todo!(),
-                Piece::Rook => todo!(),
+    fn apply(&mut self, m: &usize) {
+        self.cells[*m] = Some(self.active);
+        self.key ^= self.zobrist.key(m * 2 + self.active);
+        self.active = 1 - self.active;
+    }
+
+    fn undo(&mut self, m: &usize) {
+        self.active = 1 - self.active;
+        self.key ^= self.zobrist.key(m * 2 + self.active);
+        self.cells[*m] = None;
+    }
+
+    fn score(&self) -> i32 {
+        0
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        let opponent = Some(1 - self.active);
+        if LINES
+            .iter()
+            .any(|line| line.iter().all(|&i| self.cells[i] == opponent))
+        {
+            Some(Outcome::Loss(0))
+        } else if self.cells.iter().all(|cell| cell.is_some()) {
+            Some(Outcome::Draw)
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn principal_variation() {
+    let mut nim = Nim::new(6);
+    let search = AlphaBetaSearch::start_search(&mut nim, 6, Duration::from_secs(60));
+    let result = search.best_so_far.unwrap();
+    assert_eq!(result.m, 2);
+    assert!(matches!(result.outcome, Outcome::Win(_)));
+    assert_eq!(result.pv[0], 2);
+    assert_eq!(result.pv.iter().sum::<usize>(), 6);
+    assert_eq!(result.pv.len() % 2, 1);
+    assert_eq!(nim, Nim::new(6));
+}
+
+#[test]
+fn search_stats() {
+    let mut nim = Nim::new(9);
+    let completed = Arc::new(Mutex::new(Vec::new()));
+    let mut search = AlphaBetaSearch::with_table_size(5, 1);
+    let observed = completed.clone();
+    search.set_observer(
+        move |result: &AlphaBetaResult<usize>, stats: &SearchStats| {
+            assert_eq!(stats.depths.last().unwrap().depth, result.depth);
+            observed.lock().unwrap().push(result.depth);
+        },
+    );
+    search.continue_search(&mut nim, Duration::from_secs(60));
+
+    assert_eq!(*completed.lock().unwrap(), vec![2, 3, 4, 5]);
+    let stats = &search.stats;
+    assert_eq!(stats.depths.len(), 4);
+    assert_eq!(
+        stats.depths.iter().map(|d| d.nodes).sum::<u64>(),
+        stats.nodes
+    );
+    assert!(stats.leaf_evaluations > 0);
+    assert!(stats.tt_hits + stats.tt_misses < stats.nodes);
+    assert!(stats.effective_branching_factor().is_some());
+}
+
+#[derive(Default)]
+struct Events {
+    new_best: Vec<(usize, usize)>,
+    timeouts: usize,
+}
+
+impl SearchObserver<usize> for Arc<Mutex<Events>> {
+    fn on_new_best(&mut self, depth: usize, m: &usize, _score: i32) {
+        self.lock().unwrap().new_best.push((depth, *m));
+    }
+
+    fn on_timeout(&mut self, _depth: usize, _stats: &SearchStats) {
+        self.lock().unwrap().timeouts += 1;
+    }
+}
+
+#[test]
+fn search_observer() {
+    let mut nim = Nim::new(6);
+    let events = Arc::new(Mutex::new(Events::default()));
+    let mut search = AlphaBetaSearch::with_table_size(4, 1);
+    search.set_observer(events.clone());
+
+    search.continue_search(&mut nim, Duration::ZERO);
+    assert!(!search.complete);
+    assert_eq!(events.lock().unwrap().timeouts, 1);
+
+    search.continue_search(&mut nim, Duration::from_secs(60));
+    assert!(search.complete);
+    let events = events.lock().unwrap();
+    assert_eq!(events.timeouts, 1);
+    assert_eq!(events.new_best.first(), Some(&(2, 1)));
+    assert_eq!(events.new_best.last(), Some(&(4, 2)));
+}
+
+#[test]
+fn try_apply() {
+    let mut nim = Nim::new(2);
+    assert_eq!(nim.try_apply(&3), Err(IllegalMove(3)));
+    assert_eq!(nim, Nim::new(2));
+    assert_eq!(nim.try_apply(&2), Ok(()));
+    assert_eq!(nim.stones, 0);
+}
+
+#[test]
+fn solve_tic_tac_toe() {
+    assert_eq!(TicTacToe::default().solve().1, Outcome::Draw);
+
+    // X can win at once on 2, or later by forking.
+    let mut model = TicTacToe::from_moves(&[0, 3, 1, 4]);
+    assert_eq!(model.solve(), (Some(2), Outcome::Win(1)));
+
+    // O took an edge, which loses to X's corner reply.
+    let mut model = TicTacToe::from_moves(&[4, 1]);
+    assert!(matches!(model.solve().1, Outcome::Win(_)));
+    assert_eq!(model, TicTacToe::from_moves(&[4, 1]));
+}
+
+#[test]
+fn prefers_faster_wins() {
+    // Nim from 5 wins by taking 1, and the opponent can't last more than 2 plies after that.
+    let mut nim = Nim::new(5);
+    let search = AlphaBetaSearch::start_search(&mut nim, 8, Duration::from_secs(60));
+    let result = search.best_so_far.unwrap();
+    assert_eq!(result.m, 1);
+    assert_eq!(result.outcome, Outcome::Win(3));
+
+    // Every move from 4 leaves the opponent a winning take.
+    let mut nim = Nim::new(4);
+    assert_eq!(nim.solve().1, Outcome::Loss(2));
+    let mut nim = Nim::new(6);
+    assert_eq!(nim.solve(), (Some(2), Outcome::Win(3)));
+}
+
+#[test]
+fn parallel_search() {
+    let mut model = TicTacToe::from_moves(&[0, 3, 1, 4]);
+    let search = AlphaBetaSearch::start_parallel_search(&mut model, 5, Duration::from_secs(60), 4);
+    assert!(search.complete);
+    let result = search.best_so_far.unwrap();
+    assert_eq!(result.m, 2);
+    assert_eq!(result.outcome, Outcome::Win(1));
+    assert_eq!(model, TicTacToe::from_moves(&[0, 3, 1, 4]));
+
+    let mut nim = Nim::new(13);
+    let search = AlphaBetaSearch::start_parallel_search(&mut nim, 13, Duration::from_secs(60), 3);
+    assert_eq!(search.best_so_far.unwrap().m, 1);
+}
+
+#[test]
+fn aspiration_windows() {
+    fn depth_scores(config: SearchConfig) -> (Vec<(usize, i32)>, SearchStats) {
+        let mut model = TicTacToe::from_moves(&[4, 1]);
+        let scores = Arc::new(Mutex::new(Vec::new()));
+        let mut search = AlphaBetaSearch::with_config(7, config);
+        let observed = scores.clone();
+        search.set_observer(
+            move |result: &AlphaBetaResult<usize>, _stats: &SearchStats| {
+                observed.lock().unwrap().push((result.depth, result.score));
+            },
+        );
+        search.continue_search(&mut model, Duration::from_secs(60));
+        assert!(search.complete);
+        let scores = scores.lock().unwrap().clone();
+        (scores, search.stats)
+    }
+
+    let (full, stats) = depth_scores(SearchConfig::default());
+    assert_eq!(stats.researches, 0);
+    let (narrow, stats) = depth_scores(SearchConfig {
+        aspiration_window: Some(1),
+        ..Default::default()
+    });
+    assert_eq!(narrow, full);
+    assert!(stats.researches > 0);
+    assert_eq!(Outcome::from_score(full.last().unwrap().1), Outcome::Win(5));
+}
+
+#[test]
+fn searches_agree() {
+    // A search stopped and resumed every few nodes has to pick up exactly where it left off, so it
+    // ends with the same move, score and line as one that ran straight through, in as many nodes.
+    fn agree<Model: GameModel + Clone + PartialEq + Debug>(model: &Model, depth: usize)
+    where
+        Model::GameMove: Debug,
+    {
+        let blocking =
+            AlphaBetaSearch::start_search(&mut model.clone(), depth, SearchLimits::default());
+        let expected = blocking.best_so_far.unwrap();
+        for slice in [1, 7, 50] {
+            let mut resumed = AlphaBetaSearch::with_table_size(depth, DEFAULT_TABLE_MB);
+            let mut resumed_model = model.clone();
+            let mut calls = 0;
+            while !resumed.complete {
+                let limits = SearchLimits {
+                    max_nodes: Some(resumed.stats.nodes + slice),
+                    ..Default::default()
+                };
+                resumed.continue_search(&mut resumed_model, limits);
+                calls += 1;
             }
+            assert_eq!(resumed_model, *model);
+            assert!(calls > 1 || blocking.stats.nodes <= slice);
+            let result = resumed.best_so_far.unwrap();
+            assert_eq!(
+                (&result.m, result.score, &result.pv),
+                (&expected.m, expected.score, &expected.pv),
+                "{:?} at depth {} in slices of {}",
+                model,
+                depth,
+                slice
+            );
+            assert_eq!(resumed.stats.nodes, blocking.stats.nodes);
         }
-        moves
     }
 
-    fn apply(&mut self, _m: &Self::GameMove) {}
+    for moves in [vec![], vec![4], vec![4, 1], vec![0, 4, 8], vec![0, 3, 1, 4]] {
+        let mut model = TicTacToe::from_moves(&moves);
+        for depth in [2, 3, 5, 9] {
+            agree(&model, depth);
+        }
+        let (solved, outcome) = model.solve();
+        let search = AlphaBetaSearch::start_search(&mut model, 9, SearchLimits::default());
+        let result = search.best_so_far.unwrap();
+        assert_eq!(Outcome::from_exact_score(result.score), outcome);
+        assert_eq!(Some(result.m), solved);
+        assert_eq!(model.search(9), solved);
+    }
+    for depth in [4, 13] {
+        agree(&Nim::new(13), depth);
+    }
+    agree(&Exchange::new(&[3, 5, 1]), 2);
+}
+
+#[test]
+fn quiescence() {
+    // Two plies in, taking the 3 for the 5 looks like the best of a bad lot. Playing out the
+    // remaining capture shows it just hands over the 1 as well.
+    let mut model = Exchange::new(&[3, 5, 1]);
+    let search = AlphaBetaSearch::start_search(&mut model, 2, Duration::from_secs(60));
+    let result = search.best_so_far.unwrap();
+    assert_eq!(result.m, Play::Pass);
+    assert_eq!(result.score, 0);
+    assert_eq!(model.search(1), Some(Play::Pass));
+    assert_eq!(model, Exchange::new(&[3, 5, 1]));
+}
+
+#[test]
+fn mcts() {
+    let mut model = TicTacToe::from_moves(&[0, 3, 1, 4]);
+    let mut search = MctsSearch::new(MctsConfig::default());
+    assert_eq!(
+        search.search(&mut model, MctsBudget::Iterations(2000)),
+        Some(2)
+    );
+    assert_eq!(search.root_visits(), 2000);
+    assert_eq!(model, TicTacToe::from_moves(&[0, 3, 1, 4]));
+    let stats = search.move_stats();
+    assert_eq!(stats.iter().map(|s| s.visits).sum::<u32>(), 2000);
+
+    let mut nim = Nim::new(5);
+    let mut search = MctsSearch::new(MctsConfig::default());
+    assert_eq!(
+        search.search(&mut nim, MctsBudget::Iterations(3000)),
+        Some(1)
+    );
+
+    // After our move and the reply, the tree for the new position is kept.
+    nim.apply(&1);
+    nim.apply(&2);
+    assert_eq!(
+        search.search(&mut nim, MctsBudget::Iterations(100)),
+        Some(2)
+    );
+    assert!(search.root_visits() > 100);
+    assert!(search.root_visits() < 3000);
+
+    // Without rollouts, only the evaluator tells the moves apart.
+    let centre = |model: &TicTacToe| match model.cells[4] {
+        Some(player) if player == model.active => 10,
+        Some(_) => -10,
+        None => 0,
+    };
+    let config = MctsConfig {
+        max_rollout_plies: 0,
+        ..Default::default()
+    };
+    let mut search = MctsSearch::with_evaluator(config, centre);
+    assert_eq!(
+        search.search(&mut TicTacToe::default(), MctsBudget::Iterations(500)),
+        Some(4)
+    );
+}
+
+#[test]
+fn search_limits() {
+    let mut nim = Nim::new(13);
+    let limits = SearchLimits {
+        max_depth: Some(4),
+        ..Default::default()
+    };
+    let mut search = AlphaBetaSearch::start_search(&mut nim, 13, limits);
+    assert!(!search.complete);
+    assert_eq!(search.best_so_far.as_ref().unwrap().depth, 4);
+    // A later call without the cap carries on to the target.
+    search.continue_search(&mut nim, SearchLimits::default());
+    assert!(search.complete);
+    assert_eq!(search.best_so_far.unwrap().depth, 13);
+
+    let limits = SearchLimits {
+        max_nodes: Some(50),
+        ..Default::default()
+    };
+    let mut search = AlphaBetaSearch::start_search(&mut nim, 13, limits.clone());
+    assert!(!search.complete);
+    let nodes = search.stats.nodes;
+    search.continue_search(&mut nim, limits);
+    assert_eq!(search.stats.nodes, nodes);
+    search.continue_search(&mut nim, Duration::from_secs(60));
+    assert!(search.complete);
+
+    // A passed soft deadline still lets the first depth finish, but no more.
+    let limits = SearchLimits {
+        soft_deadline: Some(Instant::now()),
+        ..Default::default()
+    };
+    let search = AlphaBetaSearch::start_search(&mut nim, 13, limits);
+    assert!(!search.complete);
+    assert_eq!(search.stats.depths.len(), 1);
+
+    let stop = Arc::new(AtomicBool::new(true));
+    let limits = SearchLimits::default().with_stop(stop);
+    let search = AlphaBetaSearch::start_parallel_search(&mut nim, 13, limits, 2);
+    assert!(!search.complete);
+    assert!(search.best_so_far.is_none());
+    assert_eq!(nim, Nim::new(13));
+}
+
+#[test]
+fn search_handle() {
+    let model = TicTacToe::from_moves(&[0, 3, 1, 4]);
+    let handle = SearchHandle::spawn(
+        AlphaBetaSearch::with_table_size(5, 1),
+        &model,
+        Duration::from_secs(60),
+        2,
+    );
+    assert_eq!(handle.key(), model.zobrist_key());
+    let search = handle.join().unwrap();
+    assert!(search.complete);
+    assert_eq!(search.best_so_far.unwrap().m, 2);
+
+    // Cancelling keeps whatever depths had finished and leaves the search to be continued.
+    let mut nim = Nim::new(40);
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut search = AlphaBetaSearch::with_table_size(40, 1);
+    let cancel = stop.clone();
+    search.set_observer(
+        move |result: &AlphaBetaResult<usize>, _stats: &SearchStats| {
+            if result.depth == 3 {
+                cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+            }
+        },
+    );
+    let handle = SearchHandle::spawn(search, &nim, SearchLimits::default().with_stop(stop), 1);
+    let mut search = handle.join().unwrap();
+    assert!(!search.complete);
+    assert_eq!(search.best_so_far.as_ref().unwrap().depth, 3);
+    let limits = SearchLimits {
+        max_depth: Some(6),
+        ..Default::default()
+    };
+    search.continue_search(&mut nim, limits);
+    assert!(!search.complete);
+    assert_eq!(search.best_so_far.unwrap().depth, 6);
+}
+
+#[test]
+fn mcts_handle() {
+    let model = TicTacToe::from_moves(&[0, 3, 1, 4]);
+    let search = Arc::new(Mutex::new(MctsSearch::new(MctsConfig::default())));
+    let handle = MctsHandle::spawn(search.clone(), &model, MctsBudget::Iterations(2000));
+    assert_eq!(handle.key(), model.zobrist_key());
+    assert_eq!(handle.join(), Some(2));
+    assert_eq!(search.lock().unwrap().root_visits(), 2000);
+
+    // Cancelling stops a search that would otherwise run for a minute, and the tree is kept.
+    let handle = MctsHandle::spawn(
+        search.clone(),
+        &model,
+        MctsBudget::Time(Duration::from_secs(60)),
+    );
+    handle.cancel();
+    let start = Instant::now();
+    assert!(handle.join().is_some());
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert!(search.lock().unwrap().root_visits() >= 2000);
+}
+
+// Forgets to take back its move count on undo.
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct LeakyNim(Nim, usize);
+
+impl ZobristKey for LeakyNim {
+    fn zobrist_key(&self) -> u64 {
+        self.0.zobrist_key()
+    }
+}
+
+impl GameModel for LeakyNim {
+    type GameMove = usize;
+
+    fn legal_moves(&self) -> Vec<usize> {
+        self.0.legal_moves()
+    }
+
+    fn apply(&mut self, m: &usize) {
+        self.0.apply(m);
+        self.1 += 1;
+    }
+
+    fn undo(&mut self, m: &usize) {
+        self.0.undo(m);
+    }
+
+    fn score(&self) -> i32 {
+        self.0.score()
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        self.0.outcome()
+    }
+}
+
+#[test]
+fn model_consistency() {
+    let mut model = TicTacToe::default();
+    let counts = (0..=6)
+        .map(|depth| perft(&mut model, depth))
+        .collect::<Vec<_>>();
+    assert_eq!(counts, vec![1, 9, 72, 504, 3024, 15120, 54720]);
+    assert_eq!(perft_checked(&model, 6), Ok(54720));
+    assert_eq!(random_playouts(&model, 50, 0), Ok(()));
+    assert_eq!(random_playouts(&Nim::new(20), 50, 0), Ok(()));
+
+    let leaky = LeakyNim(Nim::new(4), 0);
+    assert_eq!(
+        check_moves(&mut leaky.clone()),
+        Err(ModelError {
+            line: vec![1],
+            inconsistency: Inconsistency::Undo,
+        })
+    );
+    let error = perft_checked(&LeakyNim(Nim::new(4), 0), 3).unwrap_err();
+    assert_eq!(error.line, vec![1]);
+    assert_eq!(random_playouts(&leaky, 1, 0).unwrap_err().line.len(), 1);
+}
+
+#[test]
+fn opening_book() {
+    let builder = BookBuilder {
+        plies: 2,
+        search_depth: 9,
+        margin: 0,
+    };
+    let mut model = TicTacToe::default();
+    let book = builder.build(&mut model);
+    assert_eq!(model, TicTacToe::default());
+    // The start and the 9 positions after one move.
+    assert_eq!(book.len(), 10);
+    // Every first move draws.
+    assert_eq!(book.moves(model.zobrist_key()).len(), 9);
+    // After a corner, only the centre holds the draw.
+    let corner = TicTacToe::from_moves(&[0]);
+    assert_eq!(
+        book.moves(corner.zobrist_key()),
+        &[BookMove {
+            index: 3,
+            weight: 1
+        }]
+    );
+
+    let book = Arc::new(book);
+    let mut search = AlphaBetaSearch::with_table_size(9, 1);
+    search.set_book(book.clone(), 0);
+    search.continue_search(&mut TicTacToe::from_moves(&[0]), Duration::from_secs(60));
+    assert!(search.complete);
+    let result = search.best_so_far.unwrap();
+    assert_eq!((result.depth, result.m), (0, 4));
+    assert!(result.from_book);
+
+    // The seed decides between the book's first moves, and the same seed always picks the same one.
+    let opening = |seed| {
+        let mut search = AlphaBetaSearch::with_table_size(9, 1);
+        search.set_book(book.clone(), seed);
+        search.continue_search(&mut TicTacToe::default(), SearchLimits::default());
+        search.best_so_far.unwrap().m
+    };
+    let openings = (0..20).map(opening).collect::<Vec<_>>();
+    assert_eq!((0..20).map(opening).collect::<Vec<_>>(), openings);
+    assert!(openings.iter().any(|&m| m != openings[0]));
+
+    // Out of the book, the search runs as usual.
+    let mut search = AlphaBetaSearch::with_table_size(5, 1);
+    search.set_book(book, 0);
+    search.continue_search(
+        &mut TicTacToe::from_moves(&[0, 3, 1, 4]),
+        Duration::from_secs(60),
+    );
+    let result = search.best_so_far.unwrap();
+    assert_eq!(result.depth, 5);
+    assert!(!result.from_book);
+}
+
+#[test]
+fn endgame_table() {
+    let mut model = TicTacToe::default();
+    let table = EndgameBuilder::default().build(&mut model);
+    assert_eq!(model, TicTacToe::default());
+    // Every reachable position where the game isn't over yet.
+    assert_eq!(table.len(), 4520);
+    assert_eq!(table.probe(model.zobrist_key()), Some(Outcome::Draw));
+    for moves in [&[0, 3, 1, 4][..], &[4, 1], &[0, 4, 8]] {
+        let mut model = TicTacToe::from_moves(moves);
+        assert_eq!(table.probe(model.zobrist_key()), Some(model.solve().1));
+    }
+
+    // Late positions only.
+    let late = EndgameBuilder { min_plies: 6 }.build(&mut TicTacToe::default());
+    assert!(late.probe(model.zobrist_key()).is_none());
+    assert!(late.len() < table.len());
 
-    fn undo(&mut self, _m: &Self::GameMove) {
-        todo!()
+    let mut models = [
+        TicTacToe::from_moves(&[0, 3]),
+        TicTacToe::from_moves(&[4, 1]),
+    ];
+    let both = EndgameBuilder::default().build_all(&mut models);
+    for mut model in models {
+        let one = EndgameBuilder::default().build(&mut model);
+        assert_eq!(
+            both.probe(model.zobrist_key()),
+            one.probe(model.zobrist_key())
+        );
+        assert!(one.len() < both.len());
+    }
+
+    // Too shallow to see the fork on its own, but the table knows how it ends.
+    let mut model = TicTacToe::from_moves(&[4, 1]);
+    let expected = model.solve();
+    let mut search = AlphaBetaSearch::with_table_size(2, 1);
+    search.set_endgame(Arc::new(table));
+    search.continue_search(&mut model, Duration::from_secs(60));
+    let result = search.best_so_far.unwrap();
+    assert_eq!(result.outcome, expected.1);
+    assert!(search.stats.endgame_hits > 0);
+}
+
+// Nim where the second player can't move with one stone left, so the game stops without an outcome.
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct StalledNim(Nim);
+
+impl ZobristKey for StalledNim {
+    fn zobrist_key(&self) -> u64 {
+        self.0.zobrist_key()
+    }
+}
+
+impl GameModel for StalledNim {
+    type GameMove = usize;
+
+    fn legal_moves(&self) -> Vec<usize> {
+        match (self.0.stones, self.0.active) {
+            (1, 1) => Vec::new(),
+            _ => self.0.legal_moves(),
+        }
+    }
+
+    fn apply(&mut self, m: &usize) {
+        self.0.apply(m);
+    }
+
+    fn undo(&mut self, m: &usize) {
+        self.0.undo(m);
     }
 
     fn score(&self) -> i32 {
-        let mut score = 0;
-        let mut has_king = HashSet::<Player>::new();
-        for (_, v) in self.board.iter() {
-            score += v.score();
-            match v.1 {
-                Piece::King => {
-                    has_king.insert(v.0);
-                }
-                _ => {}
+        self.0.score()
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        self.0.outcome()
+    }
+}
+
+#[test]
+fn endgame_table_dead_ends() {
+    let table = EndgameBuilder::default().build(&mut StalledNim(Nim::new(3)));
+    let key = |stones, active| Nim { stones, active }.zobrist_key();
+    // Neither the dead end nor anything that can reach it is solved.
+    assert_eq!(table.probe(key(1, 1)), None);
+    assert_eq!(table.probe(key(3, 0)), None);
+    // Every line from here ends in a win or a loss.
+    assert_eq!(table.probe(key(1, 0)), Some(Outcome::Win(1)));
+    assert_eq!(table.probe(key(2, 1)), Some(Outcome::Win(1)));
+    assert_eq!(table.len(), 2);
+}
+
+#[test]
+fn evaluators() {
+    // TicTacToe scores every unfinished position 0, so on its own the search takes the first move.
+    let search =
+        AlphaBetaSearch::start_search(&mut TicTacToe::default(), 2, Duration::from_secs(60));
+    assert_eq!(search.best_so_far.unwrap().m, 0);
+
+    let centre = |model: &TicTacToe| match model.cells[4] {
+        Some(player) if player == model.active => 10,
+        Some(_) => -10,
+        None => 0,
+    };
+    let mut search = AlphaBetaSearch::with_evaluator(2, SearchConfig::default(), centre);
+    search.continue_search(&mut TicTacToe::default(), Duration::from_secs(60));
+    assert_eq!(search.best_so_far.unwrap().m, 4);
+
+    let noise = Noise {
+        evaluator: ModelScore,
+        amount: 5,
+        seed: 1,
+    };
+    let model = TicTacToe::from_moves(&[4]);
+    let score = noise.evaluate(&model);
+    assert!((-5..=5).contains(&score));
+    assert_eq!(noise.evaluate(&model), score);
+    let scores = (0..9)
+        .map(|m| noise.evaluate(&TicTacToe::from_moves(&[m])))
+        .collect::<HashSet<_>>();
+    assert!(scores.len() > 1);
+}
+
+#[test]
+fn difficulty() {
+    // Every first move draws, so they're all within any margin of the best.
+    let config = SearchConfig {
+        root_margin: 1,
+        ..Default::default()
+    };
+    let mut search = AlphaBetaSearch::with_config(9, config);
+    search.continue_search(&mut TicTacToe::default(), Duration::from_secs(60));
+    let result = search.best_so_far.unwrap();
+    assert_eq!(result.near_best.len(), 9);
+    assert!(result.near_best.iter().all(|&(_, score)| score == 0));
+    let mut search = AlphaBetaSearch::with_config(9, SearchConfig::default());
+    search.continue_search(&mut TicTacToe::default(), Duration::from_secs(60));
+    assert!(search.best_so_far.unwrap().near_best.is_empty());
+
+    // Only the immediate win is near the best.
+    let mut model = TicTacToe::from_moves(&[0, 3, 1, 4]);
+    let tt = Arc::new(TranspositionTable::new(1));
+    let mut search = Difficulty::EASY.search(SearchConfig::default(), tt, ModelScore, 1);
+    search.continue_search(&mut model, SearchLimits::default());
+    let result = search.best_so_far.unwrap();
+    assert_eq!(
+        result.near_best.iter().map(|&(m, _)| m).collect::<Vec<_>>(),
+        vec![2]
+    );
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let careless = Difficulty {
+        blunder_chance: 1.0,
+        ..Difficulty::EASY
+    };
+    let moves = (0..50)
+        .map(|_| careless.choose(&model, &result, &mut rng))
+        .collect::<HashSet<_>>();
+    assert!(moves.len() > 1 && moves.iter().all(|m| model.legal_moves().contains(m)));
+    for _ in 0..50 {
+        assert_eq!(Difficulty::HARD.choose(&model, &result, &mut rng), 2);
+    }
+}
+
+#[test]
+fn expectimax() {
+    // Plain expectimax, to check the pruned search against.
+    fn expected(model: &mut Race, depth: usize, ply: usize) -> f64 {
+        if let Some(outcome) = model.outcome() {
+            return outcome.score_at(ply) as f64;
+        }
+        let events = model.chance_events();
+        if !events.is_empty() {
+            let mut sum = 0.;
+            for (roll, p) in events {
+                model.apply_chance(&roll);
+                sum += p * expected(model, depth, ply);
+                model.undo_chance(&roll);
             }
+            return sum.round();
         }
+        if depth == 0 {
+            return model.score() as f64;
+        }
+        let mut best = f64::MIN;
+        for m in model.legal_moves() {
+            model.apply(&m);
+            best = best.max(-expected(model, depth - 1, ply + 1));
+            model.undo(&m);
+        }
+        best
+    }
 
-        for player in has_king {
-            score += 1000 * player.score();
+    for totals in [[0, 0], [3, 5], [6, 9], [9, 2]] {
+        let mut model = Race::new(totals, 10);
+        for depth in [1, 3, 6] {
+            let mut search = ExpectimaxSearch::new(ExpectimaxConfig::default());
+            let result = search
+                .search(&mut model, depth, SearchLimits::default())
+                .unwrap();
+            assert_eq!(result.score as f64, expected(&mut model, depth, 0));
+            assert_eq!(model, Race::new(totals, 10));
         }
+    }
+
+    let mut search = ExpectimaxSearch::new(ExpectimaxConfig::default());
+    // One step from the finish, stepping wins outright.
+    let result = search.search(&mut Race::new([9, 0], 10), 4, SearchLimits::default());
+    assert_eq!(result.unwrap().m, Step::Safe);
+    // Stepping lets the opponent step home, so only the gamble has a chance.
+    let result = search.search(&mut Race::new([6, 9], 10), 4, SearchLimits::default());
+    assert_eq!(result.unwrap().m, Step::Gamble);
+    let mut rolling = Race::new([0, 0], 10);
+    rolling.rolling = true;
+    assert!(search
+        .search(&mut rolling, 4, SearchLimits::default())
+        .is_none());
 
-        -self.active.score() * score
+    // A seeded game plays out the same way every time.
+    let play = |seed| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut model = Race::new([0, 0], 10);
+        let mut game = Vec::new();
+        while model.outcome().is_none() {
+            match model.sample_chance(&mut rng) {
+                Some(roll) => {
+                    model.apply_chance(&roll);
+                    game.push(format!("{}", roll));
+                }
+                None => {
+                    let mut search = ExpectimaxSearch::new(ExpectimaxConfig::default());
+                    let m = search
+                        .search(&mut model, 3, SearchLimits::default())
+                        .unwrap()
+                        .m;
+                    model.apply(&m);
+                    game.push(format!("{:?}", m));
+                }
+            }
+        }
+        game
+    };
+    assert_eq!(play(7), play(7));
+}
+
+#[test]
+fn hidden_information() {
+    // The 5 was led and the player to move holds the 6 and the 2. Ducking with the 2 can only draw,
+    // while taking the trick with the 6 also wins whenever the leader's last card is the 1.
+    let views = [[1], [3], [4]].map(|leader| {
+        HighCard {
+            hands: [leader.to_vec(), vec![2, 6]],
+            played: vec![5],
+        }
+        .view()
+    });
+    assert!(views.iter().all(|view| *view == views[0]));
+    let view = &views[0];
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..20 {
+        let sample = view.determinize(&mut rng);
+        assert_eq!(sample.view(), *view);
+        assert_eq!(sample.hands[0].len(), 1);
+        assert!(!sample.hands[0].contains(&5) && !sample.hands[0].contains(&6));
     }
+
+    let mut search = DeterminizedSearch::new(DeterminizedConfig::default());
+    assert_eq!(search.search(view, SearchLimits::default()), Some(6));
+    assert_eq!(search.samples, 20);
+    let scores = search.move_scores();
+    assert_eq!(scores.iter().find(|(m, _)| *m == 2).unwrap().1, 0.);
+    assert!(scores.iter().find(|(m, _)| *m == 6).unwrap().1 > 0.);
+    let mut limited = DeterminizedSearch::new(DeterminizedConfig::default());
+    let limits = SearchLimits {
+        max_nodes: Some(10),
+        ..Default::default()
+    };
+    limited.search(view, limits);
+    assert!(limited.samples < 20);
+
+    let mut search = IsmctsSearch::new(MctsConfig::default());
+    assert_eq!(search.search(view, MctsBudget::Iterations(2000)), Some(6));
+    assert_eq!(search.root_visits(), 2000);
+
+    // Without rollouts, an evaluator that likes ducking gets its way until the tree reaches the
+    // real ends of the game.
+    let config = MctsConfig {
+        max_rollout_plies: 0,
+        ..Default::default()
+    };
+    let ducked = |model: &HighCard| match (model.played.get(1), model.active()) {
+        (Some(2), 1) => 10,
+        (Some(2), _) => -10,
+        _ => 0,
+    };
+    let mut search = IsmctsSearch::with_evaluator(config, ducked);
+    assert_eq!(search.search(view, MctsBudget::Iterations(20)), Some(2));
+
+    // Bots that only see their own views play a whole seeded game the same way every time.
+    let play = |seed| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut deck = (1..=6).collect::<Vec<u8>>();
+        deck.shuffle(&mut rng);
+        let mut model = HighCard {
+            hands: [deck[..2].to_vec(), deck[2..4].to_vec()],
+            played: Vec::new(),
+        };
+        model.hands.iter_mut().for_each(|hand| hand.sort());
+        let mut determinized = DeterminizedSearch::new(DeterminizedConfig {
+            seed,
+            ..Default::default()
+        });
+        let mut ismcts = IsmctsSearch::new(MctsConfig {
+            seed,
+            ..Default::default()
+        });
+        while model.outcome().is_none() {
+            let view = model.view();
+            let m = match view.player {
+                0 => determinized.search(&view, SearchLimits::default()),
+                _ => ismcts.search(&view, MctsBudget::Iterations(200)),
+            };
+            model.try_apply(&m.unwrap()).unwrap();
+        }
+        model.played
+    };
+    assert_eq!(play(3), play(3));
 }
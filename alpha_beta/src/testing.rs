@@ -0,0 +1,169 @@
+// Checks that a model's `legal_moves`, `apply` and `undo` agree with each other, for use in the
+// model's own tests.
+use std::{
+    error::Error,
+    fmt::{self, Debug, Display},
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::{GameModel, MAX_PLIES};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Inconsistency {
+    // `legal_moves` listed the same move twice.
+    Duplicate,
+    // `try_apply` rejected a move from `legal_moves`.
+    Rejected,
+    // Undoing the move didn't restore the model.
+    Undo,
+    // Undoing the move restored the model but not its key.
+    Key,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelError<Move> {
+    // The moves from the starting position, ending with the one that went wrong.
+    pub line: Vec<Move>,
+    pub inconsistency: Inconsistency,
+}
+
+impl<Move: Debug> Display for ModelError<Move> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} after {:?}", self.inconsistency, self.line)
+    }
+}
+
+impl<Move: Debug> Error for ModelError<Move> {}
+
+// Counts the positions `depth` plies from `model`, not counting past the end of a game. Comparing
+// against known counts catches moves that are missing or shouldn't be there.
+pub fn perft<Model: GameModel>(model: &mut Model, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    if model.outcome().is_some() {
+        return 0;
+    }
+    let mut nodes = 0;
+    for m in model.legal_moves() {
+        model.apply(&m);
+        nodes += perft(model, depth - 1);
+        model.undo(&m);
+    }
+    nodes
+}
+
+// Checks each move in the position: it's only listed once, `try_apply` accepts it, and undoing it
+// restores both the model and its key. The model is left as it was, even on failure.
+pub fn check_moves<Model: GameModel + Eq + Clone>(
+    model: &mut Model,
+) -> Result<(), ModelError<Model::GameMove>> {
+    let before = model.clone();
+    let key = model.zobrist_key();
+    let moves = model.legal_moves();
+    for (i, m) in moves.iter().enumerate() {
+        let inconsistency = if moves[..i].contains(m) {
+            Some(Inconsistency::Duplicate)
+        } else if model.try_apply(m).is_err() {
+            Some(Inconsistency::Rejected)
+        } else {
+            model.undo(m);
+            if *model != before {
+                Some(Inconsistency::Undo)
+            } else if model.zobrist_key() != key {
+                Some(Inconsistency::Key)
+            } else {
+                None
+            }
+        };
+        if let Some(inconsistency) = inconsistency {
+            *model = before;
+            return Err(ModelError {
+                line: vec![*m],
+                inconsistency,
+            });
+        }
+    }
+    Ok(())
+}
+
+// `perft`, running `check_moves` on every position it passes through.
+pub fn perft_checked<Model: GameModel + Eq + Clone>(
+    model: &Model,
+    depth: usize,
+) -> Result<u64, ModelError<Model::GameMove>> {
+    fn walk<Model: GameModel + Eq + Clone>(
+        model: &mut Model,
+        depth: usize,
+    ) -> Result<u64, ModelError<Model::GameMove>> {
+        if depth == 0 {
+            return Ok(1);
+        }
+        if model.outcome().is_some() {
+            return Ok(0);
+        }
+        check_moves(model)?;
+        let mut nodes = 0;
+        for m in model.legal_moves() {
+            model.apply(&m);
+            let result = walk(model, depth - 1);
+            model.undo(&m);
+            nodes += result.map_err(|mut error| {
+                error.line.insert(0, m);
+                error
+            })?;
+        }
+        Ok(nodes)
+    }
+    walk(&mut model.clone(), depth)
+}
+
+// Plays `games` random games from `model` until they end, running `check_moves` on every position
+// along the way, then undoes each game move by move to make sure it gets back to the start.
+pub fn random_playouts<Model: GameModel + Eq + Clone>(
+    model: &Model,
+    games: usize,
+    seed: u64,
+) -> Result<(), ModelError<Model::GameMove>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut model = model.clone();
+    let start = model.clone();
+    let key = model.zobrist_key();
+    for _ in 0..games {
+        let mut line = Vec::new();
+        while line.len() < MAX_PLIES as usize && model.outcome().is_none() {
+            check_moves(&mut model).map_err(|mut error| {
+                line.append(&mut error.line);
+                ModelError {
+                    line: line.clone(),
+                    inconsistency: error.inconsistency,
+                }
+            })?;
+            match model.legal_moves().choose(&mut rng) {
+                Some(&m) => {
+                    model.apply(&m);
+                    line.push(m);
+                }
+                None => break,
+            }
+        }
+        for m in line.iter().rev() {
+            model.undo(m);
+        }
+        let inconsistency = if model != start {
+            Some(Inconsistency::Undo)
+        } else if model.zobrist_key() != key {
+            Some(Inconsistency::Key)
+        } else {
+            None
+        };
+        if let Some(inconsistency) = inconsistency {
+            return Err(ModelError {
+                line,
+                inconsistency,
+            });
+        }
+    }
+    Ok(())
+}
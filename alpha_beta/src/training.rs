@@ -0,0 +1,355 @@
+use std::{
+    io::{self, Read, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::{
+    file::{self, invalid, read_bytes, Format},
+    AlphaBetaSearch, Evaluator, GameModel, SearchConfig, SearchLimits, TranspositionTable,
+    MAX_PLIES,
+};
+
+const SAMPLES_FORMAT: Format = Format {
+    magic: *b"ABSP",
+    version: 1,
+    name: "a self-play dataset",
+};
+const WEIGHTS_FORMAT: Format = Format {
+    magic: *b"ABWT",
+    version: 1,
+    name: "a weights file",
+};
+
+// Describes a position as numbers for a linear evaluation to weigh, for the player to move.
+pub trait Features<Model: GameModel> {
+    // The same number of features for every position.
+    fn extract(&self, model: &Model) -> Vec<f64>;
+}
+
+// What a linear evaluation multiplies each feature by.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Weights(pub Vec<f64>);
+
+impl Weights {
+    // The number of weights, then each as an f64.
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        WEIGHTS_FORMAT.write_header(&mut writer)?;
+        writer.write_all(&(self.0.len() as u32).to_le_bytes())?;
+        for weight in self.0.iter() {
+            writer.write_all(&weight.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn read(mut reader: impl Read) -> io::Result<Weights> {
+        WEIGHTS_FORMAT.read_header(&mut reader)?;
+        let count = u32::from_le_bytes(read_bytes(&mut reader)?);
+        (0..count)
+            .map(|_| read_f64(&mut reader))
+            .collect::<io::Result<_>>()
+            .map(Weights)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        file::save(path, |writer| self.write(writer))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Weights> {
+        file::load(path, Weights::read)
+    }
+}
+
+fn read_f64(reader: &mut impl Read) -> io::Result<f64> {
+    read_bytes(reader).map(f64::from_le_bytes)
+}
+
+fn dot(features: &[f64], weights: &Weights) -> f64 {
+    features
+        .iter()
+        .zip(weights.0.iter())
+        .map(|(x, w)| x * w)
+        .sum()
+}
+
+// Scores positions as the weighted sum of their features. Missing weights count as 0.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LinearEvaluator<F> {
+    pub features: F,
+    pub weights: Weights,
+}
+
+impl<Model: GameModel, F: Features<Model>> Evaluator<Model> for LinearEvaluator<F> {
+    fn evaluate(&self, model: &Model) -> i32 {
+        dot(&self.features.extract(model), &self.weights).round() as i32
+    }
+}
+
+// A position from a finished game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub features: Vec<f64>,
+    // How the game ended for the player to move: 1 for a win, 0.5 for a draw and 0 for a loss.
+    pub result: f64,
+}
+
+// Positions to tune weights on. Only their features are kept, so changing the features means
+// playing the games again.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dataset {
+    pub samples: Vec<Sample>,
+}
+
+impl Dataset {
+    // The features per sample and the number of samples, then each sample's result in half points
+    // as a byte and its features as f64s.
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        let count = self
+            .samples
+            .first()
+            .map_or(0, |sample| sample.features.len());
+        SAMPLES_FORMAT.write_header(&mut writer)?;
+        writer.write_all(&(count as u32).to_le_bytes())?;
+        writer.write_all(&(self.samples.len() as u64).to_le_bytes())?;
+        for sample in self.samples.iter() {
+            debug_assert_eq!(sample.features.len(), count);
+            writer.write_all(&[(sample.result * 2.).round() as u8])?;
+            for feature in sample.features.iter() {
+                writer.write_all(&feature.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read(mut reader: impl Read) -> io::Result<Dataset> {
+        SAMPLES_FORMAT.read_header(&mut reader)?;
+        let count = u32::from_le_bytes(read_bytes(&mut reader)?);
+        let len = u64::from_le_bytes(read_bytes(&mut reader)?);
+        let mut samples = Vec::new();
+        for _ in 0..len {
+            let result = read_bytes::<1>(&mut reader)?;
+            if result[0] > 2 {
+                return Err(invalid("result out of range"));
+            }
+            let features = (0..count)
+                .map(|_| read_f64(&mut reader))
+                .collect::<io::Result<_>>()?;
+            samples.push(Sample {
+                features,
+                result: result[0] as f64 / 2.,
+            });
+        }
+        Ok(Dataset { samples })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        file::save(path, |writer| self.write(writer))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Dataset> {
+        file::load(path, Dataset::read)
+    }
+}
+
+// Plays games between copies of one search and records every position after the opening with how
+// the game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelfPlay {
+    pub games: usize,
+    // Random moves at the start of each game. The search always plays the same moves from the same
+    // position, so these are all that make the games differ.
+    pub opening_plies: usize,
+    pub depth: usize,
+    pub seed: u64,
+}
+
+impl Default for SelfPlay {
+    fn default() -> Self {
+        SelfPlay {
+            games: 100,
+            opening_plies: 6,
+            depth: 4,
+            seed: 0,
+        }
+    }
+}
+
+impl SelfPlay {
+    pub fn generate<Model, Eval>(
+        &self,
+        start: &Model,
+        evaluator: Eval,
+        features: &impl Features<Model>,
+    ) -> Dataset
+    where
+        Model: GameModel + Clone,
+        Eval: Evaluator<Model> + Clone,
+    {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let config = SearchConfig {
+            table_mb: 4,
+            ..Default::default()
+        };
+        let mut dataset = Dataset::default();
+        for _ in 0..self.games {
+            // Kept for the whole game, so each move starts from what the last one found.
+            let tt = Arc::new(TranspositionTable::new(config.table_mb));
+            let mut model = start.clone();
+            for _ in 0..self.opening_plies {
+                match model.legal_moves().choose(&mut rng) {
+                    Some(m) if model.outcome().is_none() => model.apply(m),
+                    _ => break,
+                }
+            }
+            let mut positions = Vec::new();
+            while model.outcome().is_none() && positions.len() < MAX_PLIES as usize {
+                tt.new_search();
+                let mut search =
+                    AlphaBetaSearch::with_table(self.depth, config, tt.clone(), evaluator.clone());
+                search.continue_search(&mut model, SearchLimits::default());
+                let Some(result) = search.best_so_far else {
+                    break;
+                };
+                positions.push(features.extract(&model));
+                model.apply(&result.m);
+            }
+            // A game that stops without an outcome is scored as it stands.
+            let score = model
+                .outcome()
+                .map_or(model.score(), |outcome| outcome.score());
+            let mut result = match score.signum() {
+                1 => 1.,
+                -1 => 0.,
+                _ => 0.5,
+            };
+            // Walk back from the end, flipping the result to each position's player to move.
+            for features in positions.into_iter().rev() {
+                result = 1. - result;
+                dataset.samples.push(Sample { features, result });
+            }
+        }
+        dataset
+    }
+}
+
+// Texel tuning: fits the weights by logistic regression, so that a logistic curve through each
+// sample's evaluation predicts how its game ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tuner {
+    // The evaluation at which a win is predicted e (about 2.7) times as likely as a loss.
+    pub scale: f64,
+    pub learning_rate: f64,
+    pub iterations: usize,
+}
+
+impl Default for Tuner {
+    fn default() -> Self {
+        Tuner {
+            scale: 400.,
+            learning_rate: 0.5,
+            iterations: 1000,
+        }
+    }
+}
+
+impl Tuner {
+    // The expected result for the player to move, from 0 to 1.
+    pub fn predict(&self, evaluation: f64) -> f64 {
+        1. / (1. + (-evaluation / self.scale).exp())
+    }
+
+    // The mean cross-entropy between the predictions and the results, which tuning minimises.
+    pub fn loss(&self, dataset: &Dataset, weights: &Weights) -> f64 {
+        let total = dataset
+            .samples
+            .iter()
+            .map(|sample| {
+                let p = self
+                    .predict(dot(&sample.features, weights))
+                    .clamp(1e-12, 1. - 1e-12);
+                -(sample.result * p.ln() + (1. - sample.result) * (1. - p).ln())
+            })
+            .sum::<f64>();
+        total / dataset.samples.len().max(1) as f64
+    }
+
+    // Gradient descent from `initial`. Each feature's steps are divided by its mean square, so
+    // features on different scales settle at the same rate.
+    pub fn tune(&self, dataset: &Dataset, initial: Weights) -> Weights {
+        let count = dataset
+            .samples
+            .first()
+            .map_or(initial.0.len(), |sample| sample.features.len());
+        let mut weights = initial;
+        weights.0.resize(count, 0.);
+        if dataset.samples.is_empty() {
+            return weights;
+        }
+        let len = dataset.samples.len() as f64;
+        let mut mean_squares = vec![0.; count];
+        for sample in dataset.samples.iter() {
+            for (square, x) in mean_squares.iter_mut().zip(sample.features.iter()) {
+                *square += x * x / len;
+            }
+        }
+        for _ in 0..self.iterations {
+            let mut gradient = vec![0.; count];
+            for sample in dataset.samples.iter() {
+                let error = self.predict(dot(&sample.features, &weights)) - sample.result;
+                for (g, x) in gradient.iter_mut().zip(sample.features.iter()) {
+                    *g += error * x / len;
+                }
+            }
+            for ((w, g), square) in weights.0.iter_mut().zip(gradient).zip(mean_squares.iter()) {
+                if *square > 0. {
+                    *w -= self.learning_rate * self.scale * g / square;
+                }
+            }
+        }
+        weights
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::{Dataset, Sample, Tuner, Weights};
+
+    #[test]
+    fn tuner() {
+        // Results drawn from a known logistic model, which tuning should find again.
+        let tuner = Tuner::default();
+        let truth = Weights(vec![150., -60.]);
+        let mut rng = StdRng::seed_from_u64(0);
+        let samples = (0..4000)
+            .map(|_| {
+                let features = vec![rng.gen_range(-3..=3) as f64, rng.gen_range(0..=10) as f64];
+                let p = tuner.predict(super::dot(&features, &truth));
+                let result = match rng.gen::<f64>() {
+                    roll if roll < p => 1.,
+                    _ => 0.,
+                };
+                Sample { features, result }
+            })
+            .collect();
+        let dataset = Dataset { samples };
+        let tuned = tuner.tune(&dataset, Weights::default());
+        assert!(tuner.loss(&dataset, &tuned) < tuner.loss(&dataset, &Weights(vec![0., 0.])));
+        for (tuned, truth) in tuned.0.iter().zip(truth.0.iter()) {
+            assert!((tuned - truth).abs() < truth.abs() * 0.2, "{:?}", tuned);
+        }
+
+        let mut bytes = Vec::new();
+        tuned.write(&mut bytes).unwrap();
+        assert_eq!(Weights::read(bytes.as_slice()).unwrap(), tuned);
+        let mut bytes = Vec::new();
+        dataset.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 17 + 4000 * 17);
+        assert_eq!(Dataset::read(bytes.as_slice()).unwrap(), dataset);
+        assert!(Dataset::read(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Weights::read(bytes.as_slice()).is_err());
+    }
+}
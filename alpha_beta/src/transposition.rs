@@ -0,0 +1,244 @@
+use std::{
+    mem::size_of,
+    sync::atomic::{AtomicU64, AtomicU8, Ordering},
+};
+
+use crate::{
+    outcome::{from_table, to_table},
+    MoveIndex,
+};
+
+pub const DEFAULT_TABLE_MB: usize = 16;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Bound {
+    Exact,
+    // The score failed high, so the true value is at least this.
+    Lower,
+    // The score failed low, so the true value is at most this.
+    Upper,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Entry {
+    age: u8,
+    pub depth: u8,
+    pub bound: Bound,
+    // Index into the position's `legal_moves()`, which keeps entries the same size for any move type.
+    pub best_move: Option<u8>,
+    // Win and loss distances are counted from this position, not the root.
+    pub score: i32,
+}
+
+impl Entry {
+    // The stored score, if it is deep enough and its bound settles the (alpha, beta) window.
+    pub fn cutoff(&self, depth: usize, ply: usize, alpha: i32, beta: i32) -> Option<i32> {
+        if (self.depth as usize) < depth {
+            return None;
+        }
+        let score = from_table(self.score, ply);
+        match self.bound {
+            Bound::Exact => Some(score),
+            Bound::Lower if score >= beta => Some(beta),
+            Bound::Upper if score <= alpha => Some(alpha),
+            _ => None,
+        }
+    }
+
+    pub fn best_move(&self) -> Option<MoveIndex> {
+        self.best_move.map(|index| index as MoveIndex)
+    }
+
+    // Score, depth, age, bound and move share one word, so an entry can be written without a lock.
+    fn pack(&self) -> u64 {
+        let bound = match self.bound {
+            Bound::Exact => 1,
+            Bound::Lower => 2,
+            Bound::Upper => 3,
+        };
+        let best_move = match self.best_move {
+            Some(index) => 1 << 8 | index as u64,
+            None => 0,
+        };
+        self.score as u32 as u64
+            | (self.depth as u64) << 32
+            | (self.age as u64) << 40
+            | bound << 48
+            | best_move << 50
+    }
+
+    // Empty slots are all zeroes, which no packed entry is since its bound is never 0.
+    fn unpack(data: u64) -> Option<Entry> {
+        let bound = match (data >> 48) & 0b11 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            3 => Bound::Upper,
+            _ => return None,
+        };
+        let best_move = match (data >> 58) & 1 {
+            1 => Some((data >> 50) as u8),
+            _ => None,
+        };
+        Some(Entry {
+            age: (data >> 40) as u8,
+            depth: (data >> 32) as u8,
+            bound,
+            best_move,
+            score: data as u32 as i32,
+        })
+    }
+}
+
+// The key is stored XORed with the data, so a slot torn by two threads writing at once fails verification.
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+impl Slot {
+    fn load(&self) -> Option<(u64, Entry)> {
+        let data = self.data.load(Ordering::Relaxed);
+        let key = self.key.load(Ordering::Relaxed) ^ data;
+        Entry::unpack(data).map(|entry| (key, entry))
+    }
+
+    fn save(&self, key: u64, entry: &Entry) {
+        let data = entry.pack();
+        self.data.store(data, Ordering::Relaxed);
+        self.key.store(key ^ data, Ordering::Relaxed);
+    }
+
+    fn clear(&self) {
+        self.data.store(0, Ordering::Relaxed);
+        self.key.store(0, Ordering::Relaxed);
+    }
+}
+
+// Slot 0 keeps the deepest entry of the current search, slot 1 always takes the newest.
+type Bucket = [Slot; 2];
+
+// Shared between threads behind an `Arc`: every method takes `&self` and no locks are held.
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    age: AtomicU8,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new(DEFAULT_TABLE_MB)
+    }
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> TranspositionTable {
+        let len = (size_mb * 1024 * 1024 / size_of::<Bucket>()).max(1);
+        TranspositionTable {
+            buckets: (0..len).map(|_| Default::default()).collect(),
+            age: AtomicU8::new(0),
+        }
+    }
+
+    // Marks existing entries as stale, so they are the first to be replaced.
+    pub fn new_search(&self) {
+        self.age.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        self.buckets.iter().flatten().for_each(Slot::clear);
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        self.buckets[self.index(key)]
+            .iter()
+            .filter_map(Slot::load)
+            .find(|&(slot_key, _)| slot_key == key)
+            .map(|(_, entry)| entry)
+    }
+
+    pub fn store(
+        &self,
+        key: u64,
+        depth: usize,
+        ply: usize,
+        score: i32,
+        bound: Bound,
+        best_move: Option<MoveIndex>,
+    ) {
+        let age = self.age.load(Ordering::Relaxed);
+        let entry = Entry {
+            age,
+            depth: depth.min(u8::MAX as usize) as u8,
+            bound,
+            // A move past the first 256 isn't kept, rather than kept as the wrong one.
+            best_move: best_move.and_then(|index| u8::try_from(index).ok()),
+            score: to_table(score, ply),
+        };
+        let bucket = &self.buckets[self.index(key)];
+        let preferred = match bucket[0].load() {
+            None => true,
+            Some((old_key, old)) => old_key == key || old.age != age || old.depth <= entry.depth,
+        };
+        if preferred {
+            bucket[0].save(key, &entry);
+        } else {
+            bucket[1].save(key, &entry);
+        }
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.buckets.len() as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bound, TranspositionTable};
+
+    #[test]
+    fn bounds() {
+        let tt = TranspositionTable::new(1);
+        tt.store(1, 3, 0, 50, Bound::Lower, Some(2));
+        let entry = tt.probe(1).unwrap();
+        assert_eq!(entry.best_move(), Some(2));
+        assert_eq!(entry.cutoff(3, 0, 0, 40), Some(40));
+        assert_eq!(entry.cutoff(3, 0, 0, 60), None);
+        assert_eq!(entry.cutoff(4, 0, 0, 40), None);
+
+        tt.store(1, 3, 0, 50, Bound::Upper, None);
+        let entry = tt.probe(1).unwrap();
+        assert_eq!(entry.cutoff(3, 0, 60, 100), Some(60));
+        assert_eq!(entry.cutoff(3, 0, 40, 100), None);
+        assert_eq!(tt.probe(1 << 32 | 1), None);
+
+        tt.store(1, 3, 0, 50, Bound::Exact, Some(300));
+        assert_eq!(tt.probe(1).unwrap().best_move(), None);
+
+        tt.store(2, 1, 0, -7, Bound::Exact, None);
+        assert_eq!(tt.probe(2).unwrap().score, -7);
+        assert_eq!(tt.probe(2).unwrap().best_move(), None);
+        tt.clear();
+        assert_eq!(tt.probe(2), None);
+    }
+
+    #[test]
+    fn replacement() {
+        let tt = TranspositionTable::new(1);
+        let len = tt.buckets.len() as u64;
+        let (a, b, c) = (1 << 32, (1 + len) << 32, (1 + 2 * len) << 32);
+
+        // A shallower entry goes to the always-replace slot and keeps the deep one.
+        tt.store(a, 8, 0, 1, Bound::Exact, None);
+        tt.store(b, 2, 0, 2, Bound::Exact, None);
+        tt.store(c, 1, 0, 3, Bound::Exact, None);
+        assert_eq!(tt.probe(a).unwrap().score, 1);
+        assert_eq!(tt.probe(b), None);
+        assert_eq!(tt.probe(c).unwrap().score, 3);
+
+        // Entries from an older search give way to newer ones regardless of depth.
+        tt.new_search();
+        tt.store(b, 2, 0, 2, Bound::Exact, None);
+        assert_eq!(tt.probe(a), None);
+        assert_eq!(tt.probe(b).unwrap().score, 2);
+    }
+}
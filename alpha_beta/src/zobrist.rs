@@ -0,0 +1,71 @@
+use std::fmt::Debug;
+
+pub const DEFAULT_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+/// A table of random keys for incremental Zobrist hashing.
+///
+/// The keys come from a seeded SplitMix64 generator, so a given seed and size always
+/// produce the same table, across runs and processes.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ZobristTable {
+    seed: u64,
+    keys: Vec<u64>,
+}
+
+impl ZobristTable {
+    pub fn new(seed: u64, size: usize) -> ZobristTable {
+        let mut state = seed;
+        ZobristTable {
+            seed,
+            keys: (0..size).map(|_| split_mix(&mut state)).collect(),
+        }
+    }
+
+    pub fn key(&self, index: usize) -> u64 {
+        self.keys[index]
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl Debug for ZobristTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ZobristTable")
+            .field("seed", &self.seed)
+            .field("len", &self.keys.len())
+            .finish()
+    }
+}
+
+fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+pub trait ZobristKey {
+    // Hash of the position, kept up to date by `apply` and `undo` rather than recomputed.
+    fn zobrist_key(&self) -> u64;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ZobristTable;
+
+    #[test]
+    fn reproducible() {
+        let a = ZobristTable::new(7, 64);
+        let b = ZobristTable::new(7, 64);
+        assert_eq!(a, b);
+        assert_ne!(a, ZobristTable::new(8, 64));
+        assert_eq!(ZobristTable::new(0, 1).key(0), 0xe220_a839_7b1d_cdaf);
+    }
+}
@@ -0,0 +1,26 @@
+// Builds the opening book the connect_4 bot loads from its assets.
+//
+// cargo run --release -p connect_4_model --example build_book -- connect_4/assets/connect_4.book [plies] [depth]
+mod common;
+
+use std::env;
+
+use alpha_beta::BookBuilder;
+use common::arg;
+use connect_4_model::Model;
+
+const USAGE: &str = "usage: build_book [path] [plies] [depth]";
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let path = arg(&args, 1, String::from("connect_4.book"), USAGE);
+    let defaults = BookBuilder::default();
+    let builder = BookBuilder {
+        plies: arg(&args, 2, defaults.plies, USAGE),
+        search_depth: arg(&args, 3, defaults.search_depth, USAGE),
+        margin: 50,
+    };
+    let book = builder.build(&mut Model::default());
+    book.save(&path).unwrap();
+    println!("Wrote {} positions to {}", book.len(), path);
+}
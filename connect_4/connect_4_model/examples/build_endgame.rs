@@ -0,0 +1,38 @@
+// Solves random late positions into the endgame table the connect_4 bot loads from its assets.
+//
+// cargo run --release -p connect_4_model --example build_endgame -- connect_4/assets/connect_4.endgame [positions] [plies]
+mod common;
+
+use std::env;
+
+use alpha_beta::{EndgameBuilder, GameModel};
+use common::arg;
+use connect_4_model::bitboard::Bitboard;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+const USAGE: &str = "usage: build_endgame [path] [positions] [plies]";
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let path = arg(&args, 1, String::from("connect_4.endgame"), USAGE);
+    let positions = arg(&args, 2, 1000, USAGE);
+    let plies = arg(&args, 3, 30, USAGE);
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut models = Vec::new();
+    // Random games mostly end early, so only the ones still going after `plies` are kept.
+    while models.len() < positions {
+        let mut model = Bitboard::default();
+        let mut played = 0;
+        while played < plies && model.outcome().is_none() {
+            let m = *model.legal_moves().choose(&mut rng).unwrap();
+            model.apply(&m);
+            played += 1;
+        }
+        if model.outcome().is_none() {
+            models.push(model);
+        }
+    }
+    let table = EndgameBuilder::default().build_all(&mut models);
+    table.save(&path).unwrap();
+    println!("Wrote {} positions to {}", table.len(), path);
+}
@@ -0,0 +1,13 @@
+use std::{process, str::FromStr};
+
+// The argument at `index`, or `default` if it's missing. Prints `usage` and exits if it doesn't parse.
+pub fn arg<T: FromStr>(args: &[String], index: usize, default: T, usage: &str) -> T {
+    match args.get(index).map(|arg| arg.parse()) {
+        None => default,
+        Some(Ok(value)) => value,
+        Some(Err(_)) => {
+            eprintln!("{}", usage);
+            process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+// Plays the bot against itself and saves the positions for `tune`.
+//
+// cargo run --release -p connect_4_model --example self_play -- connect_4.samples [games] [depth]
+mod common;
+
+use std::env;
+
+use alpha_beta::SelfPlay;
+use common::arg;
+use connect_4_model::{
+    evaluators::{LineFeatures, Threats},
+    Model,
+};
+
+const USAGE: &str = "usage: self_play [path] [games] [depth]";
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let path = arg(&args, 1, String::from("connect_4.samples"), USAGE);
+    let defaults = SelfPlay::default();
+    let self_play = SelfPlay {
+        games: arg(&args, 2, defaults.games, USAGE),
+        depth: arg(&args, 3, defaults.depth, USAGE),
+        ..defaults
+    };
+    let dataset = self_play.generate(&Model::default(), Threats, &LineFeatures);
+    dataset.save(&path).unwrap();
+    println!("Wrote {} positions to {}", dataset.samples.len(), path);
+}
@@ -0,0 +1,26 @@
+// Fits the evaluation's weights to positions from `self_play`, for the connect_4 bot to load.
+//
+// cargo run --release -p connect_4_model --example tune -- connect_4.samples connect_4/assets/connect_4.weights
+mod common;
+
+use std::env;
+
+use alpha_beta::{Dataset, Tuner};
+use common::arg;
+use connect_4_model::evaluators::LineFeatures;
+
+const USAGE: &str = "usage: tune [samples] [path]";
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let samples = arg(&args, 1, String::from("connect_4.samples"), USAGE);
+    let path = arg(&args, 2, String::from("connect_4.weights"), USAGE);
+    let dataset = Dataset::load(samples).unwrap();
+    let tuner = Tuner::default();
+    let initial = LineFeatures::threat_weights();
+    println!("Loss before: {}", tuner.loss(&dataset, &initial));
+    let weights = tuner.tune(&dataset, initial);
+    println!("Loss after: {}", tuner.loss(&dataset, &weights));
+    println!("Weights: {:?}", weights.0);
+    weights.save(path).unwrap();
+}
@@ -0,0 +1,446 @@
+use std::{
+    fmt::Debug,
+    hash::Hash,
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, Shr},
+    sync::Arc,
+};
+
+use alpha_beta::{
+    Features, GameModel, IllegalMove, Outcome, ZobristKey, ZobristTable, DEFAULT_SEED,
+};
+
+use crate::{
+    evaluators::LineFeatures,
+    types::{Owner, Player, Position},
+    Model, Move,
+};
+
+// The integers a board can be packed into.
+pub trait Bits:
+    Copy
+    + Eq
+    + Hash
+    + Debug
+    + Send
+    + Sync
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitXor<Output = Self>
+    + BitAndAssign
+    + BitOrAssign
+    + BitXorAssign
+    + Not<Output = Self>
+    + Shl<usize, Output = Self>
+    + Shr<usize, Output = Self>
+{
+    const BITS: usize;
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn count_ones(self) -> u32;
+}
+
+macro_rules! impl_bits {
+    ($($t:ty),*) => {
+        $(impl Bits for $t {
+            const BITS: usize = <$t>::BITS as usize;
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+
+            fn count_ones(self) -> u32 {
+                <$t>::count_ones(self)
+            }
+        })*
+    };
+}
+
+impl_bits!(u64, u128);
+
+// Shifts that run off the end leave nothing, rather than overflowing.
+fn up<B: Bits>(bits: B, amount: usize) -> B {
+    if amount < B::BITS {
+        bits << amount
+    } else {
+        B::ZERO
+    }
+}
+
+fn down<B: Bits>(bits: B, amount: usize) -> B {
+    if amount < B::BITS {
+        bits >> amount
+    } else {
+        B::ZERO
+    }
+}
+
+// Connect N on any board that fits in `B`, keeping each player's pieces as one integer. Each column
+// takes `height + 1` bits from the bottom up, and the spare bit at the top keeps lines from
+// wrapping into the next column, so a shift by the right stride follows a line in any direction.
+// Moves and keys are the same as `Model`'s, so searches, books and tables carry over on 7x6.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bitboard<B = u64> {
+    width: usize,
+    height: usize,
+    connect: usize,
+    pieces: [B; 2],
+    // The next free row in each column.
+    heights: Vec<usize>,
+    // Every playable square, which masks out the spare bits.
+    board_mask: B,
+    // Squares on odd rows, counting the bottom row as 1.
+    odd_rows: B,
+    active_player: Player,
+    winner: Option<Player>,
+    hash: u64,
+    zobrist: Arc<ZobristTable>,
+}
+
+impl Default for Bitboard<u64> {
+    fn default() -> Self {
+        Bitboard::new(7, 6, 4)
+    }
+}
+
+impl<B: Bits> Bitboard<B> {
+    // Panics if the board doesn't fit in `B`, which takes `width * (height + 1)` bits.
+    pub fn new(width: usize, height: usize, connect: usize) -> Bitboard<B> {
+        assert!(
+            width * (height + 1) <= B::BITS,
+            "a {}x{} board doesn't fit in {} bits",
+            width,
+            height,
+            B::BITS
+        );
+        assert!(connect > 0);
+        let mut board_mask = B::ZERO;
+        let mut odd_rows = B::ZERO;
+        for x in 0..width {
+            for y in 0..height {
+                let bit = up(B::ONE, x * (height + 1) + y);
+                board_mask |= bit;
+                if y % 2 == 0 {
+                    odd_rows |= bit;
+                }
+            }
+        }
+        Bitboard {
+            width,
+            height,
+            connect,
+            pieces: [B::ZERO; 2],
+            heights: vec![0; width],
+            board_mask,
+            odd_rows,
+            active_player: Player::Red,
+            winner: None,
+            hash: 0,
+            zobrist: Arc::new(ZobristTable::new(DEFAULT_SEED, width * height * 2)),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn connect(&self) -> usize {
+        self.connect
+    }
+
+    pub fn active_player(&self) -> Player {
+        self.active_player
+    }
+
+    pub fn winner(&self) -> Option<Player> {
+        self.winner
+    }
+
+    pub fn owner(&self, pos: Position) -> Owner {
+        let bit = self.bit(pos);
+        match (
+            self.pieces[0] & bit != B::ZERO,
+            self.pieces[1] & bit != B::ZERO,
+        ) {
+            (true, _) => Owner::Owned(Player::Red),
+            (_, true) => Owner::Owned(Player::Blue),
+            _ => Owner::None,
+        }
+    }
+
+    fn bit(&self, pos: Position) -> B {
+        up(B::ONE, pos.x * (self.height + 1) + pos.y)
+    }
+
+    // The shifts that step along a column, a row and the two diagonals.
+    fn strides(&self) -> [usize; 4] {
+        [1, self.height + 1, self.height, self.height + 2]
+    }
+
+    fn has_line(&self, pieces: B) -> bool {
+        self.strides().iter().any(|&stride| {
+            let mut run = pieces;
+            for _ in 1..self.connect {
+                run &= down(run, stride);
+            }
+            run != B::ZERO
+        })
+    }
+
+    // Empty squares that would finish a line for `pieces`, whether or not they can be played yet.
+    fn threats(&self, pieces: B) -> B {
+        let empty = self.board_mask & !(self.pieces[0] | self.pieces[1]);
+        let mut threats = B::ZERO;
+        for stride in self.strides() {
+            // Lines starting at each bit with every square but the `gap`th already taken.
+            let mut before = self.board_mask;
+            for gap in 0..self.connect {
+                let mut starts = before;
+                for i in gap + 1..self.connect {
+                    starts &= down(pieces, i * stride);
+                }
+                threats |= up(starts, gap * stride);
+                before &= down(pieces, gap * stride);
+            }
+        }
+        threats & empty
+    }
+
+    fn square_key(&self, m: &Move) -> u64 {
+        let Move(pos, player) = m;
+        self.zobrist
+            .key((pos.x * self.height + pos.y) * 2 + player.index())
+    }
+}
+
+impl<B: Bits> ZobristKey for Bitboard<B> {
+    fn zobrist_key(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl<B: Bits> GameModel for Bitboard<B> {
+    type GameMove = Move;
+
+    // In `Model`'s order, so the move indices in books and tables mean the same moves: left to
+    // right, then the first block swapped to the front, then the first win.
+    fn legal_moves(&self) -> Vec<Move> {
+        if self.winner.is_some() {
+            return Vec::new();
+        }
+        let mut moves = (0..self.width)
+            .filter(|&x| self.heights[x] < self.height)
+            .map(|x| Move(Position::new(x, self.heights[x]), self.active_player))
+            .collect::<Vec<_>>();
+        let active = self.active_player.index();
+        for pieces in [self.pieces[1 - active], self.pieces[active]] {
+            let threats = self.threats(pieces);
+            if let Some(i) = moves
+                .iter()
+                .position(|m| threats & self.bit(m.0) != B::ZERO)
+            {
+                moves.swap(0, i);
+            }
+        }
+        moves
+    }
+
+    fn apply(&mut self, m: &Move) {
+        let result = self.try_apply(m);
+        debug_assert!(result.is_ok(), "{:?}", result);
+    }
+
+    fn try_apply(&mut self, m: &Move) -> Result<(), IllegalMove<Move>> {
+        let Move(pos, player) = *m;
+        if self.winner.is_some()
+            || player != self.active_player
+            || pos.x >= self.width
+            || pos.y != self.heights[pos.x]
+            || pos.y >= self.height
+        {
+            return Err(IllegalMove(*m));
+        }
+        let index = player.index();
+        self.pieces[index] |= self.bit(pos);
+        self.heights[pos.x] += 1;
+        self.hash ^= self.square_key(m);
+        if self.has_line(self.pieces[index]) {
+            self.winner = Some(player);
+        }
+        self.active_player = player.opponent();
+        Ok(())
+    }
+
+    fn undo(&mut self, m: &Move) {
+        let Move(pos, player) = *m;
+        let index = player.index();
+        self.pieces[index] ^= self.bit(pos);
+        self.heights[pos.x] -= 1;
+        self.hash ^= self.square_key(m);
+        self.winner = None;
+        self.active_player = player;
+    }
+
+    // Like `Model::score`: 100 for each square that would finish one of the player's lines, less
+    // the opponent's.
+    fn score(&self) -> i32 {
+        match self.winner {
+            Some(player) if player == self.active_player => 10000,
+            Some(_) => -10000,
+            None => {
+                let active = self.active_player.index();
+                let mine = self.threats(self.pieces[active]).count_ones() as i32;
+                let theirs = self.threats(self.pieces[1 - active]).count_ones() as i32;
+                100 * (mine - theirs)
+            }
+        }
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        match self.winner {
+            Some(player) if player == self.active_player => Some(Outcome::Win(0)),
+            Some(_) => Some(Outcome::Loss(0)),
+            None if self.heights.iter().all(|&y| y == self.height) => Some(Outcome::Draw),
+            None => None,
+        }
+    }
+}
+
+// The same position as a board-sized connect 4.
+impl From<&Model> for Bitboard<u64> {
+    fn from(model: &Model) -> Self {
+        let (width, height) = model.dimensions;
+        let mut bitboard = Bitboard::new(width, height, 4);
+        for (x, (filled, column)) in model.squares.iter().enumerate() {
+            for (y, owner) in column.iter().enumerate().take(*filled) {
+                if let Owner::Owned(player) = owner {
+                    let index = player.index();
+                    let pos = Position::new(x, y);
+                    bitboard.pieces[index] |= bitboard.bit(pos);
+                    bitboard.hash ^= bitboard.square_key(&Move(pos, *player));
+                }
+            }
+            bitboard.heights[x] = *filled;
+        }
+        bitboard.active_player = model.active_player;
+        bitboard.winner = [Player::Red, Player::Blue]
+            .into_iter()
+            .find(|player| bitboard.has_line(bitboard.pieces[player.index()]));
+        bitboard
+    }
+}
+
+impl<B: Bits> Features<Bitboard<B>> for LineFeatures {
+    fn extract(&self, model: &Bitboard<B>) -> Vec<f64> {
+        let active = model.active_player.index();
+        let mut features = vec![0.; 3];
+        for (pieces, sign) in [(model.pieces[active], 1.), (model.pieces[1 - active], -1.)] {
+            let threats = model.threats(pieces);
+            features[0] += sign * (threats & model.odd_rows).count_ones() as f64;
+            features[1] += sign * (threats & !model.odd_rows).count_ones() as f64;
+            let center = (model.width as i32 - 1) / 2;
+            let column = model.board_mask & down(!B::ZERO, B::BITS - model.height);
+            for x in 0..model.width {
+                let value = (center + 1 - (x as i32 - center).abs()) as f64 / (center + 1) as f64;
+                let count = (pieces & up(column, x * (model.height + 1))).count_ones();
+                features[2] += sign * value * count as f64;
+            }
+        }
+        features
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alpha_beta::{
+        testing::{perft, perft_checked, random_playouts},
+        AlphaBetaSearch, Features, GameModel, Outcome, SearchLimits, ZobristKey,
+    };
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+    use crate::{
+        evaluators::LineFeatures,
+        types::{Owner, Player, Position},
+        Model, Move,
+    };
+
+    use super::Bitboard;
+
+    #[test]
+    fn matches_model() {
+        let mut bitboard = Bitboard::default();
+        let counts = (0..=5)
+            .map(|depth| perft(&mut bitboard, depth))
+            .collect::<Vec<_>>();
+        assert_eq!(counts, vec![1, 7, 49, 343, 2401, 16807]);
+        assert_eq!(perft_checked(&bitboard, 3), Ok(343));
+        assert_eq!(random_playouts(&bitboard, 20, 0), Ok(()));
+
+        // Random games reach the same positions, keys and results either way.
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..50 {
+            let mut model = Model::default();
+            let mut bitboard = Bitboard::default();
+            while model.outcome().is_none() {
+                let moves = model.legal_moves();
+                assert_eq!(bitboard.legal_moves(), moves);
+                let m = *moves.choose(&mut rng).unwrap();
+                model.apply(&m);
+                bitboard.apply(&m);
+                assert_eq!(bitboard.zobrist_key(), model.zobrist_key());
+                assert_eq!(bitboard.outcome(), model.outcome());
+                assert_eq!(bitboard.score(), model.score());
+                assert_eq!(
+                    LineFeatures.extract(&bitboard),
+                    LineFeatures.extract(&model)
+                );
+                assert_eq!(Bitboard::from(&model), bitboard);
+            }
+            assert!(bitboard.legal_moves().is_empty());
+        }
+    }
+
+    #[test]
+    fn connect_5() {
+        let mut board = Bitboard::<u128>::new(9, 7, 5);
+        assert_eq!(board.legal_moves().len(), 9);
+        // Red builds along the bottom while Blue stacks on top.
+        for x in 0..4 {
+            board.apply(&Move(Position::new(x, 0), Player::Red));
+            board.apply(&Move(Position::new(x, 1), Player::Blue));
+        }
+        assert_eq!(board.owner(Position::new(3, 1)), Owner::Owned(Player::Blue));
+        assert_eq!(board.outcome(), None);
+        // Each has one square to finish on, Red's on an odd row and Blue's on an even one.
+        assert_eq!(board.score(), 0);
+        assert_eq!(LineFeatures.extract(&board)[..2], [1., -1.]);
+        // Red's winning square comes first, and the search takes it.
+        let win = Move(Position::new(4, 0), Player::Red);
+        assert_eq!(board.legal_moves()[0], win);
+        let search = AlphaBetaSearch::start_search(&mut board, 3, SearchLimits::default());
+        assert_eq!(search.best_so_far.unwrap().m, win);
+        board.apply(&win);
+        assert_eq!(board.winner(), Some(Player::Red));
+        assert_eq!(board.outcome(), Some(Outcome::Loss(0)));
+        board.undo(&win);
+
+        // Red climbs a diagonal from (0, 0) to (4, 4), finishing on the last move.
+        let mut board = Bitboard::<u128>::new(9, 7, 5);
+        for x in [0, 1, 1, 2, 2, 3, 2, 3, 3, 4, 4, 4, 3, 4, 4] {
+            assert_eq!(board.winner(), None);
+            let m = board
+                .legal_moves()
+                .into_iter()
+                .find(|m| m.0.x == x)
+                .unwrap();
+            board.apply(&m);
+        }
+        assert_eq!(board.winner(), Some(Player::Red));
+        assert_eq!(
+            random_playouts(&Bitboard::<u128>::new(9, 7, 5), 10, 0),
+            Ok(())
+        );
+    }
+}
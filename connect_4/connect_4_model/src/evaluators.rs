@@ -0,0 +1,177 @@
+use alpha_beta::{Evaluator, Features, GameModel, LinearEvaluator, Weights};
+
+use crate::{
+    types::{Owner, Player},
+    Model,
+};
+
+// Counts open lines each player could still finish, the same as `Model::score`.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Threats;
+
+impl Evaluator<Model> for Threats {
+    fn evaluate(&self, model: &Model) -> i32 {
+        model.score()
+    }
+}
+
+// Pieces are worth more the closer they are to the middle column, since more lines run through it.
+#[derive(Clone, Copy, Debug)]
+pub struct CenterControl {
+    // What a piece in the middle column is worth. Each column further out takes a share off.
+    pub weight: i32,
+}
+
+impl Default for CenterControl {
+    fn default() -> Self {
+        CenterControl { weight: 10 }
+    }
+}
+
+impl Evaluator<Model> for CenterControl {
+    fn evaluate(&self, model: &Model) -> i32 {
+        let width = model.dimensions.0 as i32;
+        let center = (width - 1) / 2;
+        model
+            .squares
+            .iter()
+            .enumerate()
+            .map(|(x, (_, column))| {
+                let value = self.weight * (center + 1 - (x as i32 - center).abs()) / (center + 1);
+                column
+                    .iter()
+                    .map(|owner| match owner {
+                        Owner::Owned(player) if *player == model.active_player => value,
+                        Owner::Owned(_) => -value,
+                        Owner::None => 0,
+                    })
+                    .sum::<i32>()
+            })
+            .sum()
+    }
+}
+
+// Both of the above, which plays like the model's score but favours the middle while nothing is
+// threatened yet.
+pub fn threats_and_center(model: &Model) -> i32 {
+    Threats.evaluate(model) + CenterControl::default().evaluate(model)
+}
+
+// For tuning: the player to move's open lines finishing on odd and on even rows, counting from the
+// bottom, and how central their pieces are, each less the opponent's.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct LineFeatures;
+
+impl LineFeatures {
+    // Weights that score the same as `Threats`, for when none have been tuned.
+    pub fn threat_weights() -> Weights {
+        Weights(vec![100., 100., 0.])
+    }
+
+    pub fn evaluator(weights: Weights) -> LinearEvaluator<LineFeatures> {
+        LinearEvaluator {
+            features: LineFeatures,
+            weights,
+        }
+    }
+}
+
+impl Features<Model> for LineFeatures {
+    fn extract(&self, model: &Model) -> Vec<f64> {
+        let tracker = &model.score_tracker;
+        let (mine, theirs) = match model.active_player {
+            Player::Red => (&tracker.red, &tracker.blue),
+            Player::Blue => (&tracker.blue, &tracker.red),
+        };
+        let mut features = vec![0.; 3];
+        for (threats, sign) in [(mine, 1.), (theirs, -1.)] {
+            for threat in threats.threats() {
+                features[threat.y % 2] += sign;
+            }
+        }
+        let width = model.dimensions.0 as i32;
+        let center = (width - 1) / 2;
+        for (x, (_, column)) in model.squares.iter().enumerate() {
+            let value = (center + 1 - (x as i32 - center).abs()) as f64 / (center + 1) as f64;
+            for owner in column.iter() {
+                features[2] += match owner {
+                    Owner::Owned(player) if *player == model.active_player => value,
+                    Owner::Owned(_) => -value,
+                    Owner::None => 0.,
+                };
+            }
+        }
+        features
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use alpha_beta::{
+        AlphaBetaSearch, Evaluator, Features, GameModel, SearchConfig, SelfPlay, Tuner,
+    };
+
+    use crate::{
+        types::{Player, Position},
+        Model, Move,
+    };
+
+    use super::{threats_and_center, CenterControl, LineFeatures, Threats};
+
+    #[test]
+    fn evaluators() {
+        let model = Model::from([Move(Position::new(3, 0), Player::Red)].iter());
+        assert_eq!(Threats.evaluate(&model), model.score());
+        assert_eq!(CenterControl::default().evaluate(&model), -10);
+        let edge = Model::from([Move(Position::new(0, 0), Player::Red)].iter());
+        assert_eq!(CenterControl::default().evaluate(&edge), -2);
+
+        let mut search =
+            AlphaBetaSearch::with_evaluator(2, SearchConfig::default(), CenterControl::default());
+        search.continue_search(&mut Model::default(), Duration::from_secs(60));
+        assert_eq!(search.best_so_far.unwrap().m.0.x, 3);
+
+        let mut search =
+            AlphaBetaSearch::with_evaluator(4, SearchConfig::default(), threats_and_center);
+        search.continue_search(&mut Model::default(), Duration::from_secs(60));
+        assert!(search.complete);
+    }
+
+    #[test]
+    fn line_features() {
+        // Red's three along the bottom can be finished on either side, both on the first row.
+        let moves = [
+            Move(Position::new(2, 0), Player::Red),
+            Move(Position::new(2, 1), Player::Blue),
+            Move(Position::new(3, 0), Player::Red),
+            Move(Position::new(3, 1), Player::Blue),
+            Move(Position::new(4, 0), Player::Red),
+        ];
+        let model = Model::from(moves.iter());
+        assert_eq!(LineFeatures.extract(&model), vec![-2., 0., -0.75]);
+        let evaluator = LineFeatures::evaluator(LineFeatures::threat_weights());
+        assert_eq!(evaluator.evaluate(&model), Threats.evaluate(&model));
+
+        let self_play = SelfPlay {
+            games: 4,
+            depth: 2,
+            ..Default::default()
+        };
+        let dataset = self_play.generate(&Model::default(), Threats, &LineFeatures);
+        assert!(dataset.samples.len() > 4);
+        assert!(dataset
+            .samples
+            .iter()
+            .all(|sample| sample.features.len() == 3));
+        let tuner = Tuner {
+            iterations: 100,
+            ..Default::default()
+        };
+        let tuned = tuner.tune(&dataset, LineFeatures::threat_weights());
+        assert!(
+            tuner.loss(&dataset, &tuned) <= tuner.loss(&dataset, &LineFeatures::threat_weights())
+        );
+    }
+}
@@ -1,9 +1,12 @@
-use alpha_beta::GameModel;
+use alpha_beta::{GameModel, IllegalMove, Outcome, ZobristKey, ZobristTable, DEFAULT_SEED};
 use score_tracker::ScoreTracker;
-use std::hash::{Hash, Hasher};
-use std::{collections::hash_map::DefaultHasher, fmt::Debug};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::Arc;
 use types::{Dimensions, Owner, Player, Position};
 
+pub mod bitboard;
+pub mod evaluators;
 mod line_tracker;
 mod score_tracker;
 mod test;
@@ -16,6 +19,7 @@ pub struct Model {
     pub active_player: Player,
     winner: Option<Player>,
     hash: u64,
+    zobrist: Arc<ZobristTable>,
     score_tracker: ScoreTracker,
 }
 
@@ -32,6 +36,10 @@ impl Default for Model {
             squares: vec![(0, vec![Owner::None; dimensions.1]); dimensions.0],
             winner: Default::default(),
             hash: Default::default(),
+            zobrist: Arc::new(ZobristTable::new(
+                DEFAULT_SEED,
+                dimensions.0 * dimensions.1 * 2,
+            )),
             score_tracker: ScoreTracker::new(&d),
         }
     }
@@ -49,6 +57,12 @@ impl Hash for Model {
     }
 }
 
+impl ZobristKey for Model {
+    fn zobrist_key(&self) -> u64 {
+        self.hash
+    }
+}
+
 impl GameModel for Model {
     type GameMove = Move;
     fn legal_moves(&self) -> Vec<Move> {
@@ -68,33 +82,29 @@ impl GameModel for Model {
     }
 
     fn apply(&mut self, m: &Move) {
-        let mr = self.predict(m);
-        match mr.0 {
+        let result = self.try_apply(m);
+        debug_assert!(result.is_ok(), "{:?}", result);
+    }
+
+    fn try_apply(&mut self, m: &Move) -> Result<(), IllegalMove<Move>> {
+        match self.predict(m).0 {
             Some(Move(pos, player)) => {
-                let mut hasher = DefaultHasher::new();
-                pos.hash(&mut hasher);
-                player.hash(&mut hasher);
-                self.hash ^= hasher.finish();
+                self.hash ^= self.square_key(m);
                 self.squares[pos.x].0 += 1;
                 self.squares[pos.x].1[pos.y] = Owner::Owned(player);
                 self.update_winner(&pos);
                 self.score_tracker.apply(m);
                 self.active_player = self.active_player.opponent();
+                Ok(())
             }
-            None => {
-                println!("Illegal move! Move: {:?}", m);
-                println!("Illegal move! Board: {:?}", self.squares);
-            }
+            None => Err(IllegalMove(*m)),
         }
     }
 
     fn undo(&mut self, m: &Move) {
-        let mut hasher = DefaultHasher::new();
         let pos = m.0;
-        pos.hash(&mut hasher);
-        m.1.hash(&mut hasher);
         self.winner = None;
-        self.hash ^= hasher.finish();
+        self.hash ^= self.square_key(m);
         self.squares[pos.x].1[pos.y] = Owner::None;
         self.squares[pos.x].0 -= 1;
         self.score_tracker.undo(m);
@@ -113,6 +123,21 @@ impl GameModel for Model {
             }
         }
     }
+
+    fn outcome(&self) -> Option<Outcome> {
+        match self.winner {
+            Some(player) if player == self.active_player => Some(Outcome::Win(0)),
+            Some(_) => Some(Outcome::Loss(0)),
+            None if self
+                .squares
+                .iter()
+                .all(|column| column.0 == self.dimensions.1) =>
+            {
+                Some(Outcome::Draw)
+            }
+            None => None,
+        }
+    }
 }
 
 impl<'a, It: Iterator<Item = &'a Move>> From<It> for Model {
@@ -127,7 +152,10 @@ impl<'a, It: Iterator<Item = &'a Move>> From<It> for Model {
 
 impl Model {
     pub fn predict(&self, m: &Move) -> MoveResult {
-        if m.1 != self.active_player {
+        if m.1 != self.active_player || self.winner.is_some() {
+            return MoveResult(None);
+        }
+        if m.0.x >= self.dimensions.0 || m.0.y >= self.dimensions.1 {
             return MoveResult(None);
         }
         if self.squares[m.0.x].1[m.0.y] != Owner::None {
@@ -139,6 +167,13 @@ impl Model {
         return MoveResult(Some(m.clone()));
     }
 
+    // The side to move follows from the number of pieces, so only squares are keyed.
+    fn square_key(&self, m: &Move) -> u64 {
+        let Move(pos, player) = m;
+        self.zobrist
+            .key((pos.x * self.dimensions.1 + pos.y) * 2 + player.index())
+    }
+
     fn update_winner(&mut self, pos: &Position) {
         let kernel = pos.kernel(self.dimensions);
         for line in kernel {
@@ -290,7 +325,13 @@ impl Position {
 
 #[cfg(test)]
 mod tests {
-    use alpha_beta::GameModel;
+    use std::time::Duration;
+
+    use alpha_beta::{
+        testing::{perft, perft_checked, random_playouts},
+        AlphaBetaSearch, GameModel, IllegalMove, MctsBudget, MctsConfig, MctsSearch, Outcome,
+        SearchConfig, ZobristKey,
+    };
 
     use crate::{
         types::{Owner, Player, Position},
@@ -339,6 +380,66 @@ mod tests {
         }
     }
 
+    #[test]
+    fn zobrist_key_transpositions() {
+        let a = [
+            Move(Position::new(0, 0), Player::Red),
+            Move(Position::new(4, 0), Player::Blue),
+            Move(Position::new(2, 0), Player::Red),
+        ];
+        let b = [
+            Move(Position::new(2, 0), Player::Red),
+            Move(Position::new(4, 0), Player::Blue),
+            Move(Position::new(0, 0), Player::Red),
+        ];
+        let mut model = Model::from(a.iter());
+        assert_eq!(model.zobrist_key(), Model::from(b.iter()).zobrist_key());
+        assert_ne!(model.zobrist_key(), Model::default().zobrist_key());
+
+        let m = model.legal_moves()[0];
+        let key = model.zobrist_key();
+        model.apply(&m);
+        assert_ne!(model.zobrist_key(), key);
+        model.undo(&m);
+        assert_eq!(model.zobrist_key(), key);
+    }
+
+    #[test]
+    fn try_apply_illegal() {
+        let mut model = Model::default();
+        let floating = Move(Position::new(0, 1), Player::Red);
+        let wrong_player = Move(Position::new(0, 0), Player::Blue);
+        assert_eq!(model.try_apply(&floating), Err(IllegalMove(floating)));
+        assert_eq!(
+            model.try_apply(&wrong_player),
+            Err(IllegalMove(wrong_player))
+        );
+        for off_board in [Position::new(7, 0), Position::new(0, 6)] {
+            let m = Move(off_board, Player::Red);
+            assert_eq!(model.try_apply(&m), Err(IllegalMove(m)));
+        }
+        assert_eq!(model, Model::default());
+        assert_eq!(
+            model.try_apply(&Move(Position::new(0, 0), Player::Red)),
+            Ok(())
+        );
+
+        // Nothing can be played once the game is won.
+        let moves = [
+            Move(Position::new(0, 0), Player::Red),
+            Move(Position::new(1, 0), Player::Blue),
+            Move(Position::new(0, 1), Player::Red),
+            Move(Position::new(1, 1), Player::Blue),
+            Move(Position::new(0, 2), Player::Red),
+            Move(Position::new(1, 2), Player::Blue),
+            Move(Position::new(0, 3), Player::Red),
+        ];
+        let mut model = Model::from(moves.iter());
+        assert_eq!(model.outcome(), Some(Outcome::Loss(0)));
+        let after = Move(Position::new(2, 0), Player::Blue);
+        assert_eq!(model.try_apply(&after), Err(IllegalMove(after)));
+    }
+
     #[test]
     fn model_from_moves() {
         let moves = [
@@ -389,6 +490,7 @@ mod tests {
         let model = Model::from(moves.iter());
         assert_eq!(model.winner, Some(Player::Red));
         assert!(model.legal_moves().is_empty());
+        assert_eq!(model.outcome(), Some(Outcome::Loss(0)));
 
         // Horizontal
         let moves = [
@@ -437,6 +539,72 @@ mod tests {
         assert_eq!(model.winner, Some(Player::Blue));
         assert!(model.legal_moves().is_empty());
     }
+    #[test]
+    fn search_finds_win() {
+        let moves = [
+            Move(Position::new(0, 0), Player::Red),
+            Move(Position::new(1, 0), Player::Blue),
+            Move(Position::new(0, 1), Player::Red),
+            Move(Position::new(1, 1), Player::Blue),
+            Move(Position::new(0, 2), Player::Red),
+            Move(Position::new(2, 0), Player::Blue),
+        ];
+        let mut model = Model::from(moves.iter());
+        let win = Move(Position::new(0, 3), Player::Red);
+
+        let search = AlphaBetaSearch::start_search(&mut model, 4, Duration::from_secs(60));
+        let result = search.best_so_far.unwrap();
+        assert_eq!(result.m, win);
+        assert_eq!(result.pv, vec![win]);
+        assert_eq!(result.outcome, Outcome::Win(1));
+        assert_eq!(model.search(4), Some(win));
+        let mut mcts = MctsSearch::new(MctsConfig::default());
+        assert_eq!(
+            mcts.search(&mut model, MctsBudget::Iterations(500)),
+            Some(win)
+        );
+        assert_eq!(model.zobrist_key(), Model::from(moves.iter()).zobrist_key());
+    }
+
+    #[test]
+    fn model_consistency() {
+        let mut model = Model::default();
+        let counts = (0..=5)
+            .map(|depth| perft(&mut model, depth))
+            .collect::<Vec<_>>();
+        assert_eq!(counts, vec![1, 7, 49, 343, 2401, 16807]);
+        assert_eq!(perft_checked(&model, 3), Ok(343));
+        assert_eq!(random_playouts(&model, 20, 0), Ok(()));
+    }
+
+    #[test]
+    fn aspiration_matches_full_window() {
+        let moves = [
+            Move(Position::new(3, 0), Player::Red),
+            Move(Position::new(2, 0), Player::Blue),
+            Move(Position::new(3, 1), Player::Red),
+            Move(Position::new(4, 0), Player::Blue),
+        ];
+        let mut model = Model::from(moves.iter());
+        let mut full = AlphaBetaSearch::with_config(6, SearchConfig::default());
+        full.continue_search(&mut model, Duration::from_secs(60));
+        let mut narrow = AlphaBetaSearch::with_config(
+            6,
+            SearchConfig {
+                aspiration_window: Some(10),
+                ..Default::default()
+            },
+        );
+        narrow.continue_search(&mut model, Duration::from_secs(60));
+
+        assert!(full.complete && narrow.complete);
+        assert_eq!(
+            narrow.best_so_far.unwrap().score,
+            full.best_so_far.unwrap().score
+        );
+        assert_eq!(full.stats.researches, 0);
+    }
+
     #[test]
     fn score_vertical() {
         let moves = [];
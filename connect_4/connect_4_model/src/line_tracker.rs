@@ -39,7 +39,12 @@ impl LineTracker {
     }
 
     pub fn score(&self) -> i32 {
-        100 * self.potential.difference(&self.occupied).count() as i32
+        100 * self.threats().count() as i32
+    }
+
+    // Empty squares that would finish a line for the player.
+    pub fn threats(&self) -> impl Iterator<Item = &Position> {
+        self.potential.difference(&self.occupied)
     }
 
     pub fn apply(&mut self, m: &Move) {
@@ -99,11 +104,10 @@ impl LineTracker {
         }
         self.owned.remove(&m.0);
 
-        if self.introduced.contains_key(&m.0) {
-            for empty in self.introduced[&m.0].iter() {
+        if let Some(introduced) = self.introduced.remove(&m.0) {
+            for empty in introduced.iter() {
                 self.potential.remove(empty);
             }
-            self.introduced.get_mut(&m.0).unwrap().clear();
         }
     }
 }
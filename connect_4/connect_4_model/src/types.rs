@@ -10,6 +10,13 @@ impl Player {
             Player::Blue => Player::Red,
         }
     }
+
+    pub fn index(&self) -> usize {
+        match self {
+            Player::Red => 0,
+            Player::Blue => 1,
+        }
+    }
 }
 
 #[derive(PartialEq, Clone, Copy, Debug, Hash, Eq)]
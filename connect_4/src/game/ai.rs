@@ -1,77 +1,297 @@
-use std::{marker::PhantomData, time::Duration};
+use std::{
+    marker::PhantomData,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
 
-use alpha_beta::{AlphaBetaSearch, GameModel};
-use bevy::prelude::*;
-use connect_4_model::{Model, Move};
+use alpha_beta::{
+    AlphaBetaSearch, Book, Difficulty, EndgameTable, GameModel, LinearEvaluator, MctsConfig,
+    MctsHandle, MctsSearch, Noise, SearchConfig, SearchHandle, TranspositionTable, Weights,
+    ZobristKey,
+};
+use bevy::{asset::FileAssetIo, prelude::*};
+use connect_4_model::{bitboard::Bitboard, evaluators::LineFeatures, Model, Move};
 use iyes_loopless::prelude::IntoConditionalSystem;
 
-use super::{actions::Actions, input::Bot, logic::MoveHistory};
-use k_utils::{util_input::handle_input, util_state::StateContraint};
-
-fn select_move(board: &mut Model, search: &mut Option<AlphaBetaSearch<Move>>) -> Option<Move> {
-    let depth = 6;
-    let m = board.search(depth);
-    match search {
-        Some(search) => search.continue_search(board, Duration::from_millis(2000)),
-        None => {
-            *search = Some(AlphaBetaSearch::start_search(
-                board,
-                depth,
-                Duration::from_millis(20),
-            ));
+use super::{
+    actions::Actions,
+    input::{Bot, BotSearch, Engine},
+    logic::MoveHistory,
+};
+use k_utils::{
+    util_input::handle_input,
+    util_search::{BackgroundSearch, SearchFinished, SearchPlugin, Searching},
+    util_state::StateContraint,
+};
+
+// Looks where the `AssetServer` finds the fonts and images, which is next to the executable unless
+// run through cargo.
+fn asset_path(name: &str) -> PathBuf {
+    FileAssetIo::get_base_path().join("assets").join(name)
+}
+
+// Built by `cargo run --release -p connect_4_model --example build_book -- connect_4/assets/connect_4.book`.
+const BOOK_FILE: &str = "connect_4.book";
+
+// The bot plays without a book if the file isn't there.
+#[derive(Resource)]
+struct OpeningBook(Option<Arc<Book>>);
+
+impl OpeningBook {
+    fn load() -> Self {
+        OpeningBook(Book::load(asset_path(BOOK_FILE)).ok().map(Arc::new))
+    }
+}
+
+// Built by `cargo run --release -p connect_4_model --example build_endgame -- connect_4/assets/connect_4.endgame`.
+const ENDGAME_FILE: &str = "connect_4.endgame";
+
+// Solved positions. The bot searches without them if the file isn't there.
+#[derive(Resource)]
+struct Endgames(Option<Arc<EndgameTable>>);
+
+impl Endgames {
+    fn load() -> Self {
+        Endgames(
+            EndgameTable::load(asset_path(ENDGAME_FILE))
+                .ok()
+                .map(Arc::new),
+        )
+    }
+}
+
+// Built by the `self_play` and `tune` examples in connect_4_model.
+const WEIGHTS_FILE: &str = "connect_4.weights";
+
+// Without a usable weights file the bot scores positions the way the model does.
+#[derive(Resource)]
+struct EvaluationWeights(Weights);
+
+impl EvaluationWeights {
+    fn load() -> Self {
+        let path = asset_path(WEIGHTS_FILE);
+        let fallback = LineFeatures::threat_weights();
+        let weights = match Weights::load(&path) {
+            // Weights tuned for other features would quietly misjudge every position.
+            Ok(weights) if weights.0.len() == fallback.0.len() => weights,
+            Ok(weights) => {
+                warn!(
+                    "{} has {} weights instead of {}, so the bot is using the threat weights",
+                    path.display(),
+                    weights.0.len(),
+                    fallback.0.len()
+                );
+                fallback
+            }
+            Err(err) => {
+                warn!(
+                    "Couldn't load {} ({}), so the bot is using the threat weights",
+                    path.display(),
+                    err
+                );
+                fallback
+            }
+        };
+        EvaluationWeights(weights)
+    }
+}
+
+// Kept from move to move, so each search starts from what the last one found.
+#[derive(Resource, Default)]
+struct BotTable(Arc<TranspositionTable>);
+
+type BotEvaluator = Noise<LinearEvaluator<LineFeatures>>;
+
+pub enum BotResult {
+    AlphaBeta(Option<AlphaBetaSearch<Move, BotEvaluator>>),
+    Mcts(Option<Move>),
+}
+
+impl BackgroundSearch for BotSearch {
+    type Output = BotResult;
+
+    fn key(&self) -> u64 {
+        match self {
+            BotSearch::AlphaBeta(search) => search.key(),
+            BotSearch::Mcts(search) => search.key(),
         }
     }
-    match search {
-        Some(unwrapped_search) => {
-            if unwrapped_search.complete {
-                match &unwrapped_search.best_so_far {
-                    Some(result) => {
-                        if !m.unwrap().eq(&result.m) {
-                            println!("MOVE DISAGREE!");
-                            println!("Good move: {:?}", m);
-                            println!("Move: {:?}, score: {}", result.m, result.score);
-                        }
-                        let m = result.m;
-                        println!("Move: {:?}, score: {}", m, result.score);
-                        *search = None;
-                        Some(m)
-                    }
-                    None => None,
-                }
-            } else {
-                None
+
+    fn is_finished(&self) -> bool {
+        match self {
+            BotSearch::AlphaBeta(search) => search.is_finished(),
+            BotSearch::Mcts(search) => search.is_finished(),
+        }
+    }
+
+    fn finish(self) -> BotResult {
+        match self {
+            BotSearch::AlphaBeta(search) => BotResult::AlphaBeta(search.join()),
+            BotSearch::Mcts(search) => BotResult::Mcts(search.join()),
+        }
+    }
+}
+
+// However deep the difficulty searches, the bot doesn't think for longer than this. Even HARD, which
+// has no time limit of its own, stops here, though its depth rarely takes this long.
+const MAX_THINKING_TIME: Duration = Duration::from_secs(2);
+
+fn start_alpha_beta(
+    board: &Model,
+    difficulty: &Difficulty,
+    book: &OpeningBook,
+    endgames: &Endgames,
+    weights: &EvaluationWeights,
+    table: &BotTable,
+) -> SearchHandle<Move, BotEvaluator> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let config = SearchConfig {
+        // One open line is worth 100, so most depths land within a line of the last one.
+        aspiration_window: Some(100),
+        ..Default::default()
+    };
+    let evaluator = LineFeatures::evaluator(weights.0.clone());
+    table.0.new_search();
+    let mut search = difficulty.search(config, table.0.clone(), evaluator, rand::random());
+    if let Some(book) = &book.0 {
+        search.set_book(book.clone(), rand::random());
+    }
+    if let Some(endgames) = &endgames.0 {
+        search.set_endgame(endgames.clone());
+    }
+    let mut limits = difficulty.limits();
+    let cap = Instant::now() + MAX_THINKING_TIME;
+    limits.deadline = Some(limits.deadline.map_or(cap, |deadline| deadline.min(cap)));
+    // The bitboard plays the same moves with the same keys, only faster.
+    SearchHandle::spawn(search, &Bitboard::from(board), limits, threads)
+}
+
+// An MCTS bot's tree, kept between its moves so each search starts from what the last one learned.
+#[derive(Component, Clone)]
+struct MctsTree(Arc<Mutex<MctsSearch<Move>>>);
+
+impl MctsTree {
+    fn new() -> Self {
+        let config = MctsConfig {
+            seed: rand::random(),
+            ..Default::default()
+        };
+        MctsTree(Arc::new(Mutex::new(MctsSearch::new(config))))
+    }
+}
+
+fn finish_search<Eval>(
+    board: &Model,
+    difficulty: &Difficulty,
+    search: &AlphaBetaSearch<Move, Eval>,
+) -> Option<Move> {
+    let result = search.best_so_far.as_ref()?;
+    info!("Best move: {:?}, outcome: {:?}", result.m, result.outcome);
+    debug!(
+        "The bot expects: {}",
+        result
+            .pv
+            .iter()
+            .map(|Move(pos, _)| pos.x.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let stats = &search.stats;
+    debug!(
+        "Nodes: {}, re-searches: {}, branching factor: {:?}, first move cutoffs: {:?}",
+        stats.nodes,
+        stats.researches,
+        stats.effective_branching_factor(),
+        stats.first_move_cutoff_rate()
+    );
+    Some(difficulty.choose(board, result, &mut rand::thread_rng()))
+}
+
+// Starts a search whenever it's a bot's turn. `SearchPlugin` checks in on it each frame.
+fn start_searches(
+    mut commands: Commands,
+    move_history: Res<MoveHistory>,
+    book: Res<OpeningBook>,
+    endgames: Res<Endgames>,
+    weights: Res<EvaluationWeights>,
+    table: Res<BotTable>,
+    bots: Query<(
+        Entity,
+        &Bot,
+        Option<&Searching<BotSearch>>,
+        Option<&MctsTree>,
+    )>,
+) {
+    let board = Model::from(move_history.0.iter());
+    for (entity, bot, searching, tree) in bots.iter() {
+        // Removing the search cancels it.
+        if board.active_player.ne(&bot.0) || board.outcome().is_some() {
+            if searching.is_some() {
+                commands.entity(entity).remove::<Searching<BotSearch>>();
             }
+            continue;
         }
-        None => {
-            println!("PANIC!");
-            None
+        // The position changed under the search, so its move no longer applies.
+        if searching.map_or(true, |searching| searching.key() != board.zobrist_key()) {
+            let search = match &bot.1 {
+                Engine::AlphaBeta(difficulty) => BotSearch::AlphaBeta(start_alpha_beta(
+                    &board, difficulty, &book, &endgames, &weights, &table,
+                )),
+                Engine::Mcts(budget) => {
+                    let tree = match tree {
+                        Some(tree) => tree.clone(),
+                        None => {
+                            let tree = MctsTree::new();
+                            commands.entity(entity).insert(tree.clone());
+                            tree
+                        }
+                    };
+                    // The bitboard plays the same moves with the same keys, only faster.
+                    BotSearch::Mcts(MctsHandle::spawn(tree.0, &Bitboard::from(&board), *budget))
+                }
+            };
+            commands.entity(entity).insert(Searching::new(search));
         }
     }
 }
 
 fn propose_moves(
-    _commands: Commands,
     move_history: Res<MoveHistory>,
-    mut bots: Query<&mut Bot>,
+    bots: Query<(&Bot, Option<&MctsTree>)>,
+    mut finished: EventReader<SearchFinished<BotSearch>>,
     mut action_ewr: EventWriter<Actions>,
 ) {
-    let mut board = Model::from(move_history.0.iter());
-    if bots.iter().all(|b| board.active_player.ne(&b.0)) {
-        return;
-    }
-
-    let mut bot = bots
-        .iter_mut()
-        .filter(|b| board.active_player.eq(&b.0))
-        .last()
-        .unwrap();
-
-    match select_move(&mut board, &mut bot.1) {
-        Some(m) => {
-            println!("Move: {:?}", m);
+    let board = Model::from(move_history.0.iter());
+    for SearchFinished {
+        entity,
+        key,
+        output,
+    } in finished.iter()
+    {
+        let Ok((bot, tree)) = bots.get(*entity) else {
+            continue;
+        };
+        if *key != board.zobrist_key() {
+            continue;
+        }
+        let m = match (&bot.1, output) {
+            (Engine::AlphaBeta(difficulty), BotResult::AlphaBeta(Some(search))) => {
+                finish_search(&board, difficulty, search)
+            }
+            (_, BotResult::Mcts(m)) => {
+                if let Some(tree) = tree {
+                    debug!("Visits: {}", tree.0.lock().unwrap().root_visits());
+                }
+                *m
+            }
+            _ => None,
+        };
+        if let Some(m) = m {
+            info!("Move: {:?}", m);
             action_ewr.send(Actions::Move(m));
         }
-        None => {}
     }
 }
 
@@ -82,6 +302,17 @@ pub struct AI<StateType: StateContraint> {
 
 impl<StateType: StateContraint> Plugin for AI<StateType> {
     fn build(&self, app: &mut App) {
-        handle_input::<StateType>(app, vec![propose_moves.into_conditional()]);
+        app.insert_resource(OpeningBook::load())
+            .insert_resource(Endgames::load())
+            .insert_resource(EvaluationWeights::load())
+            .init_resource::<BotTable>();
+        app.add_plugin(SearchPlugin::<BotSearch>::default());
+        handle_input::<StateType>(
+            app,
+            vec![
+                start_searches.into_conditional(),
+                propose_moves.into_conditional(),
+            ],
+        );
     }
 }
@@ -1,8 +1,10 @@
 use std::marker::PhantomData;
 
-use alpha_beta::{AlphaBetaSearch, GameModel};
+use alpha_beta::{
+    Difficulty, GameModel, LinearEvaluator, MctsBudget, MctsHandle, Noise, SearchHandle,
+};
 use bevy::prelude::*;
-use connect_4_model::{types::Player, Model, Move};
+use connect_4_model::{evaluators::LineFeatures, types::Player, Model, Move};
 
 use iyes_loopless::prelude::IntoConditionalSystem;
 use k_utils::{
@@ -54,8 +56,31 @@ impl<StateType: StateContraint> Plugin for Input<StateType> {
 #[derive(Component, PartialEq, Clone, Copy, Debug)]
 pub struct Human(pub Player);
 
+// How a bot picks its moves.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Engine {
+    AlphaBeta(Difficulty),
+    // Monte Carlo tree search, for as long as the budget allows.
+    Mcts(MctsBudget),
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::AlphaBeta(Difficulty::default())
+    }
+}
+
+pub enum BotSearch {
+    AlphaBeta(SearchHandle<Move, Noise<LinearEvaluator<LineFeatures>>>),
+    Mcts(MctsHandle<Move>),
+}
+
 #[derive(Component)]
-pub struct Bot(pub Player, pub Option<AlphaBetaSearch<Move>>);
+pub struct Bot(pub Player, pub Engine);
+
+// The engine picked in the main menu, for the next game's bot.
+#[derive(Resource, Default, Clone, Copy, Debug)]
+pub struct BotEngine(pub Engine);
 
 #[derive(Component, PartialEq, Clone, Copy, Hash, Eq, Debug)]
 pub struct Column(pub i32);
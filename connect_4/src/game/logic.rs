@@ -1,9 +1,10 @@
 use std::marker::PhantomData;
 
+use alpha_beta::GameModel;
 use bevy::prelude::*;
 use connect_4_model::{
     types::{Owner, Position},
-    Model, Move, MoveResult,
+    Model, Move,
 };
 use iyes_loopless::{
     condition::ConditionalSystemDescriptor,
@@ -23,7 +24,7 @@ use self::mut_deref::setup_board;
 
 use derive_more::{Deref, Into};
 
-use super::{actions::Actions, plugin::Game};
+use super::{actions::Actions, input::BotEngine, plugin::Game};
 
 #[derive(Component, Deref, PartialEq, Clone, Copy, Hash, Eq, Debug, Into)]
 pub struct PositionW(Position);
@@ -34,7 +35,7 @@ pub struct OwnerW(Owner);
 pub mod mut_deref {
     use std::ops::DerefMut;
 
-    use crate::game::input::{Bot, Column, Human};
+    use crate::game::input::{Bot, BotEngine, Column, Human};
 
     use super::{Owner, OwnerW, PositionW};
     use bevy::prelude::*;
@@ -46,7 +47,7 @@ pub mod mut_deref {
         }
     }
 
-    pub fn setup_board(mut commands: Commands) {
+    pub fn setup_board(mut commands: Commands, engine: Res<BotEngine>) {
         for column in 0..7 {
             commands.spawn(Column(column));
         }
@@ -58,7 +59,7 @@ pub mod mut_deref {
             }
         }
         commands.spawn(Human(Player::Red));
-        commands.spawn(Bot(Player::Blue, None));
+        commands.spawn(Bot(Player::Blue, engine.0));
     }
 }
 
@@ -84,15 +85,17 @@ fn apply_move(
                 _ => {}
             },
             Actions::Move(m) => {
-                let model = Model::from(history.0.iter());
-                let mr = model.predict(&m);
-                if let MoveResult(Some(m)) = mr {
-                    history.0.push(m);
-                    for (pos, mut owner) in board.iter_mut() {
-                        if m.0.eq(pos) {
-                            owner.0 = Owner::Owned(m.1);
+                let mut model = Model::from(history.0.iter());
+                match model.try_apply(m) {
+                    Ok(()) => {
+                        history.0.push(*m);
+                        for (pos, mut owner) in board.iter_mut() {
+                            if m.0.eq(pos) {
+                                owner.0 = Owner::Owned(m.1);
+                            }
                         }
                     }
+                    Err(illegal) => warn!("{}", illegal),
                 }
             }
         }
@@ -117,6 +120,7 @@ impl<StateType: StateContraint> Plugin for Logic<StateType> {
     fn build(&self, app: &mut App) {
         app.add_enter_system(UtilState::<StateType>::Enter, setup_board);
         add_util_resource::<StateType, MoveHistory>(app);
+        app.init_resource::<BotEngine>();
         handle_actions::<StateType>(app, apply_move.into_conditional());
     }
 }
@@ -1,7 +1,7 @@
 mod actions;
 mod ai;
 mod graphics;
-mod input;
+pub mod input;
 mod logic;
 pub mod plugin;
 mod ui;
@@ -2,11 +2,17 @@ use bevy::prelude::{Commands, EventReader};
 use iyes_loopless::{condition::ConditionalSystemDescriptor, prelude::IntoConditionalSystem};
 use k_utils::{util_action::Action, util_button::State, util_plugin::UtilPlugin};
 
-use crate::{game::plugin::Game, main_menu::plugin::MainMenu};
+use crate::{
+    game::{
+        input::{BotEngine, Engine},
+        plugin::Game,
+    },
+    main_menu::plugin::MainMenu,
+};
 
 #[derive(Clone, Copy)]
 pub enum Actions {
-    StartGame(State),
+    StartGame(Engine, State),
 }
 
 impl Action for Actions {
@@ -18,8 +24,9 @@ impl Action for Actions {
 fn apply_move(mut commands: Commands, mut action_ewr: EventReader<Actions>) {
     for action in action_ewr.iter() {
         match action {
-            Actions::StartGame(s) => match s {
+            Actions::StartGame(engine, s) => match s {
                 k_utils::util_button::State::JustReleased(_) => {
+                    commands.insert_resource(BotEngine(*engine));
                     MainMenu::exit_to::<Game>(&mut commands);
                     return;
                 }
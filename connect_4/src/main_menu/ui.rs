@@ -1,5 +1,6 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, time::Duration};
 
+use alpha_beta::{Difficulty, MctsBudget};
 use bevy::prelude::*;
 use iyes_loopless::prelude::*;
 
@@ -10,34 +11,50 @@ use k_utils::{
 };
 
 use super::{actions::Actions, plugin::MainMenu};
+use crate::game::input::Engine;
 
 const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
 const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
 
+const ENGINES: [(&str, Engine); 4] = [
+    ("Easy", Engine::AlphaBeta(Difficulty::EASY)),
+    ("Medium", Engine::AlphaBeta(Difficulty::MEDIUM)),
+    ("Hard", Engine::AlphaBeta(Difficulty::HARD)),
+    (
+        "Monte Carlo",
+        Engine::Mcts(MctsBudget::Time(Duration::from_secs(1))),
+    ),
+];
+
 pub fn spawn_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
     let commands = &mut commands;
-    let button = add_button::<Actions>(
-        commands,
-        &asset_server,
-        UtilButtonConfig {
-            size: Size::new(Val::Px(300.0), Val::Px(65.0)),
-            text: "Enter Game".to_string(),
-        },
-        Box::new(|s| Actions::StartGame(*s)),
-    );
     let root = commands
         .spawn(NodeBundle {
             style: Style {
+                flex_direction: FlexDirection::Column,
                 size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
                 justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
                 ..default()
             },
             ..Default::default()
         })
         .id();
 
-    commands.entity(root).add_child(button);
+    // Each button starts a game against a bot using that engine.
+    for (name, engine) in ENGINES {
+        let button = add_button::<Actions>(
+            commands,
+            &asset_server,
+            UtilButtonConfig {
+                size: Size::new(Val::Px(300.0), Val::Px(65.0)),
+                text: name.to_string(),
+            },
+            Box::new(move |s| Actions::StartGame(engine, *s)),
+        );
+        commands.entity(root).add_child(button);
+    }
 }
 
 fn update_ui(
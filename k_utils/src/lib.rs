@@ -6,6 +6,7 @@ pub mod util_graphics;
 pub mod util_input;
 pub mod util_plugin;
 pub mod util_resource;
+pub mod util_search;
 pub mod util_stages;
 pub mod util_state;
 pub mod util_systems;
@@ -0,0 +1,109 @@
+use std::marker::PhantomData;
+
+use alpha_beta::{AlphaBetaSearch, SearchHandle};
+use bevy::prelude::*;
+
+// A search running off the main thread, which can be checked on without waiting for it.
+pub trait BackgroundSearch: Send + Sync + 'static {
+    type Output: Send + Sync + 'static;
+
+    // The key of the position being searched.
+    fn key(&self) -> u64;
+
+    fn is_finished(&self) -> bool;
+
+    // Only called once the search has finished, so this doesn't block.
+    fn finish(self) -> Self::Output;
+}
+
+impl<Move, Eval> BackgroundSearch for SearchHandle<Move, Eval>
+where
+    Move: Clone + Send + Sync + 'static,
+    Eval: Send + Sync + 'static,
+{
+    type Output = Option<AlphaBetaSearch<Move, Eval>>;
+
+    fn key(&self) -> u64 {
+        SearchHandle::key(self)
+    }
+
+    fn is_finished(&self) -> bool {
+        SearchHandle::is_finished(self)
+    }
+
+    fn finish(self) -> Self::Output {
+        self.join()
+    }
+}
+
+// A search for an entity, such as a bot. It stays after finishing, so the entity isn't given the
+// same search again, until it's replaced or removed. Either cancels a handle still running.
+#[derive(Component)]
+pub struct Searching<W: BackgroundSearch> {
+    key: u64,
+    work: Option<W>,
+}
+
+impl<W: BackgroundSearch> Searching<W> {
+    pub fn new(work: W) -> Searching<W> {
+        Searching {
+            key: work.key(),
+            work: Some(work),
+        }
+    }
+
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.work.is_none()
+    }
+}
+
+// Sent once when an entity's search finishes.
+pub struct SearchFinished<W: BackgroundSearch> {
+    pub entity: Entity,
+    pub key: u64,
+    pub output: W::Output,
+}
+
+// Polls every `Searching<W>` each frame, so nothing in the render loop waits on a search.
+pub struct SearchPlugin<W: BackgroundSearch> {
+    _phantom: PhantomData<W>,
+}
+
+impl<W: BackgroundSearch> Default for SearchPlugin<W> {
+    fn default() -> Self {
+        SearchPlugin {
+            _phantom: PhantomData,
+        }
+    }
+}
+
+fn poll_searches<W: BackgroundSearch>(
+    mut searches: Query<(Entity, &mut Searching<W>)>,
+    mut finished: EventWriter<SearchFinished<W>>,
+) {
+    for (entity, mut searching) in searches.iter_mut() {
+        if searching
+            .work
+            .as_ref()
+            .is_some_and(|work| work.is_finished())
+        {
+            let output = searching.work.take().unwrap().finish();
+            finished.send(SearchFinished {
+                entity,
+                key: searching.key,
+                output,
+            });
+        }
+    }
+}
+
+impl<W: BackgroundSearch> Plugin for SearchPlugin<W> {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SearchFinished<W>>()
+            .add_system(poll_searches::<W>);
+    }
+}
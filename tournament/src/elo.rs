@@ -0,0 +1,244 @@
+use std::fmt::{self, Display};
+
+// How a game went for one of the engines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl GameResult {
+    // The same game for the other engine.
+    pub fn flip(self) -> GameResult {
+        match self {
+            GameResult::Win => GameResult::Loss,
+            GameResult::Draw => GameResult::Draw,
+            GameResult::Loss => GameResult::Win,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Results {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Results {
+    pub fn add(&mut self, result: GameResult) {
+        match result {
+            GameResult::Win => self.wins += 1,
+            GameResult::Draw => self.draws += 1,
+            GameResult::Loss => self.losses += 1,
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    // Points per game, with a draw worth half a win.
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.) / self.games().max(1) as f64
+    }
+
+    // The variance of a single game's points.
+    fn variance(&self) -> f64 {
+        let score = self.score();
+        let games = self.games().max(1) as f64;
+        (self.wins as f64 * (1. - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2))
+            / games
+    }
+
+    // None until a game has been played, and while one engine has won every game, since no finite
+    // difference explains that.
+    pub fn elo(&self) -> Option<Elo> {
+        let score = self.score();
+        if self.games() == 0 || score == 0. || score == 1. {
+            return None;
+        }
+        // The 95% interval on the score, mapped through to Elo. Its ends stop half a game short of
+        // 0 and 1, where the difference would be infinite.
+        let games = self.games() as f64;
+        let margin = 1.96 * (self.variance() / games).sqrt();
+        let (min, max) = (0.5 / games, 1. - 0.5 / games);
+        let lower = elo_difference((score - margin).clamp(min, max));
+        let upper = elo_difference((score + margin).clamp(min, max));
+        Some(Elo {
+            difference: elo_difference(score),
+            error: (upper - lower) / 2.,
+        })
+    }
+}
+
+impl Display for Results {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "+{} ={} -{}", self.wins, self.draws, self.losses)
+    }
+}
+
+// The Elo difference that makes `score` the expected points per game. Infinite for 0 and 1.
+pub fn elo_difference(score: f64) -> f64 {
+    400. * (score / (1. - score)).log10()
+}
+
+// The points per game expected against a player `elo` points weaker.
+pub fn expected_score(elo: f64) -> f64 {
+    1. / (1. + 10f64.powf(-elo / 400.))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Elo {
+    pub difference: f64,
+    // Half the width of the 95% confidence interval around `difference`.
+    pub error: f64,
+}
+
+impl Display for Elo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:+.1} ± {:.1}", self.difference, self.error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SprtStatus {
+    Continue,
+    // The first engine is no better than `elo0`.
+    AcceptH0,
+    // The first engine is better by at least `elo1`.
+    AcceptH1,
+}
+
+// A sequential probability ratio test between two Elo differences, which stops a match as soon as
+// the games so far favour one strongly enough. `alpha` and `beta` are the chances of accepting
+// the wrong one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        Sprt {
+            elo0: 0.,
+            elo1: 10.,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+}
+
+impl Sprt {
+    // The log-likelihood ratio of H1 over H0, from the normal approximation to the results.
+    pub fn llr(&self, results: &Results) -> f64 {
+        let variance = results.variance();
+        if results.games() == 0 || variance == 0. {
+            return 0.;
+        }
+        let score0 = expected_score(self.elo0);
+        let score1 = expected_score(self.elo1);
+        results.games() as f64 * (score1 - score0) * (2. * results.score() - score0 - score1)
+            / (2. * variance)
+    }
+
+    // The LLRs at which H0 and H1 are accepted.
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1. - self.alpha)).ln(),
+            ((1. - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    pub fn status(&self, results: &Results) -> SprtStatus {
+        let llr = self.llr(results);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtStatus::AcceptH1
+        } else if llr <= lower {
+            SprtStatus::AcceptH0
+        } else {
+            SprtStatus::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{elo_difference, expected_score, Results, Sprt, SprtStatus};
+
+    #[test]
+    fn elo() {
+        assert_eq!(elo_difference(0.5), 0.);
+        assert!((elo_difference(0.75) - 190.85).abs() < 0.01);
+        assert!((expected_score(elo_difference(0.3)) - 0.3).abs() < 1e-9);
+        assert_eq!(Results::default().elo(), None);
+
+        let even = Results {
+            wins: 30,
+            draws: 40,
+            losses: 30,
+        };
+        let elo = even.elo().unwrap();
+        assert_eq!(elo.difference, 0.);
+        assert!(elo.error > 30. && elo.error < 60.);
+        let more = Results {
+            wins: 300,
+            draws: 400,
+            losses: 300,
+        };
+        assert!(more.elo().unwrap().error < elo.error / 3.);
+        assert_eq!(format!("{}", even), "+30 =40 -30");
+
+        let sweep = Results {
+            wins: 10,
+            ..Default::default()
+        };
+        assert_eq!(sweep.elo(), None);
+        let lopsided = Results {
+            wins: 9,
+            draws: 0,
+            losses: 1,
+        };
+        let elo = lopsided.elo().unwrap();
+        assert!(elo.difference > 0. && elo.error.is_finite());
+    }
+
+    #[test]
+    fn sprt() {
+        let sprt = Sprt::default();
+        let (lower, upper) = sprt.bounds();
+        assert!((upper - 2.944).abs() < 0.001);
+        assert_eq!(lower, -upper);
+
+        let draws = Results {
+            draws: 100,
+            ..Default::default()
+        };
+        assert_eq!(sprt.status(&draws), SprtStatus::Continue);
+        let close = Results {
+            wins: 11,
+            draws: 2,
+            losses: 9,
+        };
+        assert_eq!(sprt.status(&close), SprtStatus::Continue);
+        let winning = Results {
+            wins: 120,
+            draws: 40,
+            losses: 40,
+        };
+        assert_eq!(sprt.status(&winning), SprtStatus::AcceptH1);
+        let losing = Results {
+            wins: 40,
+            draws: 40,
+            losses: 120,
+        };
+        assert_eq!(sprt.status(&losing), SprtStatus::AcceptH0);
+    }
+}
@@ -0,0 +1,113 @@
+use std::{sync::Arc, time::Duration};
+
+use alpha_beta::{
+    AlphaBetaSearch, Evaluator, GameModel, MctsBudget, MctsConfig, MctsSearch, ModelScore,
+    SearchConfig, SearchLimits, TranspositionTable,
+};
+
+// A bot that can play in a tournament.
+pub trait Engine<Model: GameModel> {
+    fn name(&self) -> &str;
+
+    // Forgets anything kept from the last game.
+    fn new_game(&mut self) {}
+
+    // None if the engine doesn't find a move, which forfeits the game.
+    fn choose(&mut self, model: &mut Model) -> Option<Model::GameMove>;
+}
+
+// An alpha-beta search to a fixed depth, keeping its table from move to move within a game.
+#[derive(Clone)]
+pub struct AlphaBetaEngine<Eval = ModelScore> {
+    pub name: String,
+    pub depth: usize,
+    pub config: SearchConfig,
+    pub evaluator: Eval,
+    // Limits for each move. Node limits play the same games on every machine, which time doesn't.
+    pub max_nodes: Option<u64>,
+    pub time: Option<Duration>,
+    tt: Arc<TranspositionTable>,
+}
+
+impl AlphaBetaEngine {
+    pub fn new(name: &str, depth: usize) -> AlphaBetaEngine {
+        AlphaBetaEngine::with_evaluator(name, depth, ModelScore)
+    }
+}
+
+impl<Eval> AlphaBetaEngine<Eval> {
+    pub fn with_evaluator(name: &str, depth: usize, evaluator: Eval) -> AlphaBetaEngine<Eval> {
+        AlphaBetaEngine {
+            name: name.to_string(),
+            depth,
+            // Every game clears a new table, so a big one only slows the games down.
+            config: SearchConfig {
+                table_mb: 4,
+                ..Default::default()
+            },
+            evaluator,
+            max_nodes: None,
+            time: None,
+            tt: Arc::new(TranspositionTable::new(4)),
+        }
+    }
+}
+
+impl<Model: GameModel, Eval: Evaluator<Model> + Clone> Engine<Model> for AlphaBetaEngine<Eval> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn new_game(&mut self) {
+        self.tt = Arc::new(TranspositionTable::new(self.config.table_mb));
+    }
+
+    fn choose(&mut self, model: &mut Model) -> Option<Model::GameMove> {
+        let limits = SearchLimits {
+            max_nodes: self.max_nodes,
+            ..self.time.map(SearchLimits::from).unwrap_or_default()
+        };
+        self.tt.new_search();
+        let mut search = AlphaBetaSearch::with_table(
+            self.depth,
+            self.config,
+            self.tt.clone(),
+            self.evaluator.clone(),
+        );
+        search.continue_search(model, limits);
+        search.best_so_far.map(|result| result.m)
+    }
+}
+
+// MCTS, keeping its tree from move to move within a game.
+pub struct MctsEngine<Move> {
+    pub name: String,
+    pub config: MctsConfig,
+    pub budget: MctsBudget,
+    search: MctsSearch<Move>,
+}
+
+impl<Move: PartialEq + Copy> MctsEngine<Move> {
+    pub fn new(name: &str, config: MctsConfig, budget: MctsBudget) -> MctsEngine<Move> {
+        MctsEngine {
+            name: name.to_string(),
+            config,
+            budget,
+            search: MctsSearch::new(config),
+        }
+    }
+}
+
+impl<Model: GameModel> Engine<Model> for MctsEngine<Model::GameMove> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn new_game(&mut self) {
+        self.search = MctsSearch::new(self.config);
+    }
+
+    fn choose(&mut self, model: &mut Model) -> Option<Model::GameMove> {
+        self.search.search(model, self.budget)
+    }
+}
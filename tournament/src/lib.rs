@@ -0,0 +1,242 @@
+use std::fmt::{self, Display};
+
+use alpha_beta::{GameModel, MAX_PLIES};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+mod elo;
+mod engine;
+
+pub use elo::{elo_difference, expected_score, Elo, GameResult, Results, Sprt, SprtStatus};
+pub use engine::{AlphaBetaEngine, Engine, MctsEngine};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TournamentConfig {
+    // The most games each pair of engines plays, rounded up to an even number.
+    pub games: u32,
+    // Random moves played from the start before the engines take over, so the games don't all
+    // repeat. Each opening is played twice, with the engines swapping sides.
+    pub opening_plies: usize,
+    pub seed: u64,
+    // Stops a match as soon as the test decides, instead of playing every game.
+    pub sprt: Option<Sprt>,
+    // Games still going after this many plies are drawn.
+    pub max_plies: usize,
+}
+
+impl Default for TournamentConfig {
+    fn default() -> Self {
+        TournamentConfig {
+            games: 100,
+            opening_plies: 4,
+            seed: 0,
+            sprt: None,
+            max_plies: MAX_PLIES as usize,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchResult {
+    pub first: String,
+    pub second: String,
+    // For the first engine.
+    pub results: Results,
+    // Where the test stood when the match ended, if there was one.
+    pub sprt: Option<SprtStatus>,
+}
+
+impl Display for MatchResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} vs {}: {}", self.first, self.second, self.results)?;
+        if let Some(elo) = self.results.elo() {
+            write!(f, ", Elo {}", elo)?;
+        }
+        if let Some(sprt) = self.sprt {
+            write!(f, ", SPRT {:?}", sprt)?;
+        }
+        Ok(())
+    }
+}
+
+// Plays `model` out between two engines, with the first moving first. The result is for the first.
+pub fn play_game<Model: GameModel + Clone>(
+    model: &Model,
+    engines: [&mut dyn Engine<Model>; 2],
+    max_plies: usize,
+) -> GameResult {
+    let mut model = model.clone();
+    let [first, second] = engines;
+    for ply in 0..max_plies {
+        // The result so far for the engine to move.
+        let result = match model.outcome() {
+            Some(outcome) => Some(outcome.score()),
+            None if model.legal_moves().is_empty() => Some(model.score()),
+            None => None,
+        }
+        .map(|score| match score.signum() {
+            1 => GameResult::Win,
+            -1 => GameResult::Loss,
+            _ => GameResult::Draw,
+        });
+        let engine = match ply % 2 {
+            0 => &mut *first,
+            _ => &mut *second,
+        };
+        let result = match result {
+            Some(result) => result,
+            None => match engine.choose(&mut model) {
+                Some(m) if model.legal_moves().contains(&m) => {
+                    model.apply(&m);
+                    continue;
+                }
+                _ => GameResult::Loss,
+            },
+        };
+        return match ply % 2 {
+            0 => result,
+            _ => result.flip(),
+        };
+    }
+    GameResult::Draw
+}
+
+// Plays up to `plies` random moves from `start`, stopping early if the game ends.
+pub fn random_opening<Model: GameModel + Clone>(
+    start: &Model,
+    plies: usize,
+    rng: &mut impl Rng,
+) -> Model {
+    let mut model = start.clone();
+    for _ in 0..plies {
+        if model.outcome().is_some() {
+            break;
+        }
+        match model.legal_moves().choose(rng) {
+            Some(m) => model.apply(m),
+            None => break,
+        }
+    }
+    model
+}
+
+// Plays engines against each other from the same starting position.
+pub struct Tournament<Model> {
+    pub config: TournamentConfig,
+    pub start: Model,
+}
+
+impl<Model: GameModel + Clone> Tournament<Model> {
+    pub fn new(config: TournamentConfig, start: Model) -> Tournament<Model> {
+        Tournament { config, start }
+    }
+
+    // Plays pairs of games from random openings until the games run out or the SPRT decides.
+    pub fn play_match(
+        &self,
+        first: &mut dyn Engine<Model>,
+        second: &mut dyn Engine<Model>,
+    ) -> MatchResult {
+        let mut rng = StdRng::seed_from_u64(self.config.seed);
+        let mut results = Results::default();
+        let mut sprt = None;
+        while results.games() < self.config.games {
+            let opening = random_opening(&self.start, self.config.opening_plies, &mut rng);
+            for swap in [false, true] {
+                first.new_game();
+                second.new_game();
+                let result = match swap {
+                    false => {
+                        play_game(&opening, [&mut *first, &mut *second], self.config.max_plies)
+                    }
+                    true => {
+                        let engines: [&mut dyn Engine<Model>; 2] = [&mut *second, &mut *first];
+                        play_game(&opening, engines, self.config.max_plies).flip()
+                    }
+                };
+                results.add(result);
+            }
+            sprt = self.config.sprt.map(|sprt| sprt.status(&results));
+            if sprt.is_some_and(|status| status != SprtStatus::Continue) {
+                break;
+            }
+        }
+        MatchResult {
+            first: first.name().to_string(),
+            second: second.name().to_string(),
+            results,
+            sprt,
+        }
+    }
+
+    // A match between every pair of engines, in the order they're given.
+    pub fn round_robin(&self, engines: &mut [Box<dyn Engine<Model>>]) -> Vec<MatchResult> {
+        let mut matches = Vec::new();
+        for second in 1..engines.len() {
+            for first in 0..second {
+                let (before, after) = engines.split_at_mut(second);
+                matches.push(self.play_match(before[first].as_mut(), after[0].as_mut()));
+            }
+        }
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alpha_beta::{MctsBudget, MctsConfig};
+    use connect_4_model::Model;
+
+    use crate::{
+        AlphaBetaEngine, Engine, MctsEngine, Sprt, SprtStatus, Tournament, TournamentConfig,
+    };
+
+    #[test]
+    fn tournament() {
+        let config = TournamentConfig {
+            games: 6,
+            ..Default::default()
+        };
+        let tournament = Tournament::new(config, Model::default());
+        let mut engines: Vec<Box<dyn Engine<Model>>> = vec![
+            Box::new(AlphaBetaEngine::new("depth 1", 1)),
+            Box::new(AlphaBetaEngine::new("depth 3", 3)),
+            Box::new(MctsEngine::new(
+                "mcts",
+                MctsConfig::default(),
+                MctsBudget::Iterations(100),
+            )),
+        ];
+        let matches = tournament.round_robin(&mut engines);
+        let pairs = matches
+            .iter()
+            .map(|result| (result.first.as_str(), result.second.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pairs,
+            [
+                ("depth 1", "depth 3"),
+                ("depth 1", "mcts"),
+                ("depth 3", "mcts")
+            ]
+        );
+        assert!(matches.iter().all(|result| result.results.games() == 6));
+        assert!(matches[0].results.score() < 0.5);
+        // Seeded engines and openings play the same match every time.
+        assert_eq!(tournament.round_robin(&mut engines), matches);
+
+        // A one-sided match stops as soon as the test is sure.
+        let config = TournamentConfig {
+            games: 1000,
+            sprt: Some(Sprt {
+                elo1: 100.,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let tournament = Tournament::new(config, Model::default());
+        let (weak, strong) = engines.split_at_mut(1);
+        let result = tournament.play_match(strong[0].as_mut(), weak[0].as_mut());
+        assert_eq!(result.sprt, Some(SprtStatus::AcceptH1));
+        assert!(result.results.games() < 100);
+    }
+}
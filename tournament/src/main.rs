@@ -0,0 +1,58 @@
+// Plays connect 4 bots against each other and prints how each match went.
+//
+// cargo run --release -p tournament -- [games] [depth]
+use std::{env, process, str::FromStr};
+
+use alpha_beta::{MctsBudget, MctsConfig};
+use connect_4_model::{
+    evaluators::{threats_and_center, CenterControl},
+    Model,
+};
+use tournament::{AlphaBetaEngine, Engine, MctsEngine, Sprt, Tournament, TournamentConfig};
+
+const USAGE: &str = "usage: tournament [games] [depth]";
+
+// The argument at `index`, or `default` if it's missing.
+fn arg<T: FromStr>(args: &[String], index: usize, default: T) -> T {
+    match args.get(index).map(|arg| arg.parse()) {
+        None => default,
+        Some(Ok(value)) => value,
+        Some(Err(_)) => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    }
+}
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let games = arg(&args, 1, 200);
+    let depth = arg(&args, 2, 4);
+    let config = TournamentConfig {
+        games,
+        sprt: Some(Sprt::default()),
+        ..Default::default()
+    };
+    let tournament = Tournament::new(config, Model::default());
+    let mut engines: Vec<Box<dyn Engine<Model>>> = vec![
+        Box::new(AlphaBetaEngine::new("threats", depth)),
+        Box::new(AlphaBetaEngine::with_evaluator(
+            "threats+center",
+            depth,
+            threats_and_center,
+        )),
+        Box::new(AlphaBetaEngine::with_evaluator(
+            "center",
+            depth,
+            CenterControl::default(),
+        )),
+        Box::new(MctsEngine::new(
+            "mcts",
+            MctsConfig::default(),
+            MctsBudget::Iterations(2000),
+        )),
+    ];
+    for result in tournament.round_robin(&mut engines) {
+        println!("{}", result);
+    }
+}